@@ -0,0 +1,24 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use metamorphosis::lazy::IntoGrouping;
+
+fn each_count(c: &mut Criterion) {
+    let mut group = c.benchmark_group("each_count_1m_u64");
+
+    group.bench_function("each_count", |b| {
+        b.iter(|| black_box((0..1_000_000u64).grouping_by(|i| i % 1_024).each_count()))
+    });
+    group.bench_function("fold", |b| {
+        b.iter(|| {
+            black_box(
+                (0..1_000_000u64)
+                    .grouping_by(|i| i % 1_024)
+                    .fold(0usize, |count, _| count + 1),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, each_count);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use metamorphosis::lazy::IntoGrouping;
+
+fn short_keys() -> Vec<String> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+
+    (0..1_000_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            format!("k{}", state % 10_000)
+        })
+        .collect()
+}
+
+fn each_count(c: &mut Criterion) {
+    let keys = short_keys();
+    let mut group = c.benchmark_group("each_count_1m_short_strings");
+
+    group.bench_function("default", |b| {
+        b.iter(|| black_box(keys.iter().grouping_by(|k| k.as_str()).each_count()))
+    });
+
+    #[cfg(feature = "fast-hash")]
+    group.bench_function("fast", |b| {
+        b.iter(|| black_box(keys.iter().grouping_by(|k| k.as_str()).each_count_fast()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, each_count);
+criterion_main!(benches);
@@ -0,0 +1,215 @@
+//! Assertions for grouped results, with a readable description of how two
+//! `HashMap<K, Vec<T>>` differ instead of two dumped maps.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Write},
+    hash::{BuildHasher, Hash},
+};
+
+/// Asserts that two grouped results hold the same keys and, per key, the same
+/// elements in the same order.
+#[macro_export]
+macro_rules! assert_groups_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        if let Some(diff) = $crate::assert::groups_diff(&$actual, &$expected) {
+            panic!("{}", diff);
+        }
+    };
+}
+
+/// Like [`assert_groups_eq!`], but compares each group's elements as a
+/// multiset, ignoring their order.
+#[macro_export]
+macro_rules! assert_groups_eq_unordered {
+    ($actual:expr, $expected:expr $(,)?) => {
+        if let Some(diff) = $crate::assert::groups_diff_unordered(&$actual, &$expected) {
+            panic!("{}", diff);
+        }
+    };
+}
+
+/// Describes how `actual` differs from `expected`, comparing groups as
+/// ordered sequences, or `None` if they are equal.
+pub fn groups_diff<K, T, S1, S2>(
+    actual: &HashMap<K, Vec<T>, S1>,
+    expected: &HashMap<K, Vec<T>, S2>,
+) -> Option<String>
+where
+    K: Debug + Eq + Hash,
+    T: Debug + PartialEq,
+    S1: BuildHasher,
+    S2: BuildHasher,
+{
+    diff_with(actual, expected, |actual, expected| {
+        if actual == expected {
+            return None;
+        }
+
+        let index = actual
+            .iter()
+            .zip(expected)
+            .position(|(actual, expected)| actual != expected)
+            .unwrap_or_else(|| actual.len().min(expected.len()));
+
+        Some(format!(
+            "expected {expected:?}, got {actual:?} (first difference at index {index})"
+        ))
+    })
+}
+
+/// Describes how `actual` differs from `expected`, comparing groups as
+/// multisets, or `None` if they are equal.
+pub fn groups_diff_unordered<K, T, S1, S2>(
+    actual: &HashMap<K, Vec<T>, S1>,
+    expected: &HashMap<K, Vec<T>, S2>,
+) -> Option<String>
+where
+    K: Debug + Eq + Hash,
+    T: Debug + PartialEq,
+    S1: BuildHasher,
+    S2: BuildHasher,
+{
+    diff_with(actual, expected, |actual, expected| {
+        let mut unexpected = actual.iter().collect::<Vec<_>>();
+        let mut missing = Vec::new();
+
+        for element in expected {
+            match unexpected
+                .iter()
+                .position(|candidate| *candidate == element)
+            {
+                Some(index) => {
+                    unexpected.swap_remove(index);
+                }
+                None => missing.push(element),
+            }
+        }
+
+        if missing.is_empty() && unexpected.is_empty() {
+            return None;
+        }
+
+        Some(format!(
+            "missing elements {missing:?}, unexpected elements {unexpected:?}"
+        ))
+    })
+}
+
+fn diff_with<K, T, S1, S2, D>(
+    actual: &HashMap<K, Vec<T>, S1>,
+    expected: &HashMap<K, Vec<T>, S2>,
+    mut group_diff: D,
+) -> Option<String>
+where
+    K: Debug + Eq + Hash,
+    S1: BuildHasher,
+    S2: BuildHasher,
+    D: FnMut(&[T], &[T]) -> Option<String>,
+{
+    // Keys are only `Debug`, so their rendering doubles as a stable order.
+    let sorted = |keys: Vec<&K>| {
+        let mut keys = keys
+            .into_iter()
+            .map(|key| format!("{key:?}"))
+            .collect::<Vec<_>>();
+
+        keys.sort_unstable();
+        keys
+    };
+    let missing = sorted(
+        expected
+            .keys()
+            .filter(|key| !actual.contains_key(key))
+            .collect(),
+    );
+    let unexpected = sorted(
+        actual
+            .keys()
+            .filter(|key| !expected.contains_key(key))
+            .collect(),
+    );
+    let mut groups = expected
+        .iter()
+        .filter_map(|(key, expected)| {
+            let diff = group_diff(actual.get(key)?, expected)?;
+
+            Some((format!("{key:?}"), diff))
+        })
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() && unexpected.is_empty() && groups.is_empty() {
+        return None;
+    }
+
+    groups.sort_unstable();
+
+    let mut message = String::from("grouped results differ:");
+
+    if !missing.is_empty() {
+        let _ = write!(message, "\n  missing keys: {}", missing.join(", "));
+    }
+    if !unexpected.is_empty() {
+        let _ = write!(message, "\n  unexpected keys: {}", unexpected.join(", "));
+    }
+    for (key, diff) in groups {
+        let _ = write!(message, "\n  key {key}: {diff}");
+    }
+
+    Some(message)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, panic};
+
+    use crate::lazy::IntoGrouping;
+
+    fn by_parity() -> HashMap<&'static str, Vec<i32>> {
+        [1, 2, 3, 4, 5]
+            .into_iter()
+            .grouping_by(|n| if n % 2 == 0 { "even" } else { "odd" })
+            .into_group_map()
+    }
+
+    #[test]
+    fn test_assert_groups_eq() {
+        assert_groups_eq!(
+            by_parity(),
+            HashMap::from([("even", vec![2, 4]), ("odd", vec![1, 3, 5])])
+        );
+        assert_groups_eq_unordered!(
+            by_parity(),
+            HashMap::from([("even", vec![4, 2]), ("odd", vec![5, 1, 3])])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "key \"odd\": expected [3, 1, 5], got [1, 3, 5]")]
+    fn test_assert_groups_eq_order() {
+        assert_groups_eq!(
+            by_parity(),
+            HashMap::from([("even", vec![2, 4]), ("odd", vec![3, 1, 5])])
+        );
+    }
+
+    #[test]
+    fn test_assert_groups_eq_unordered_message() {
+        let panic = panic::catch_unwind(|| {
+            assert_groups_eq_unordered!(
+                by_parity(),
+                HashMap::from([("odd", vec![5, 1, 7]), ("zero", vec![0])])
+            );
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert_eq!(
+            message,
+            "grouped results differ:\n  \
+             missing keys: \"zero\"\n  \
+             unexpected keys: \"even\"\n  \
+             key \"odd\": missing elements [7], unexpected elements [3]"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Grouping of CSV rows read with the `csv` crate, keyed by a column or a
+//! computed key. Records are owned [`StringRecord`]s, and errors carry the
+//! position of the record that caused them.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    fmt,
+    hash::Hash,
+    io,
+};
+
+use ::csv::{Position, Reader, StringRecord};
+
+use crate::maps;
+
+/// Groups the records of `reader` by the string value of `column`, which is
+/// either a header name or a zero-based index.
+pub fn group_records_by<R>(
+    reader: Reader<R>,
+    column: impl Into<Column>,
+) -> RecordGrouping<R, ColumnKey>
+where
+    R: io::Read,
+{
+    RecordGrouping {
+        reader,
+        key: ColumnKey {
+            column: column.into(),
+            index: None,
+        },
+    }
+}
+
+/// Groups the records of `reader` by the key `key_selector` computes for
+/// them.
+pub fn group_records_by_with<R, K, F>(
+    reader: Reader<R>,
+    key_selector: F,
+) -> RecordGrouping<R, KeySelector<F>>
+where
+    R: io::Read,
+    F: FnMut(&StringRecord) -> K,
+{
+    RecordGrouping {
+        reader,
+        key: KeySelector(key_selector),
+    }
+}
+
+/// Column a record is grouped by, see [`group_records_by`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_owned())
+    }
+}
+
+impl From<String> for Column {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{name:?}"),
+            Self::Index(index) => write!(f, "#{index}"),
+        }
+    }
+}
+
+/// Key of [`group_records_by`]: the value of a column.
+#[derive(Debug)]
+pub struct ColumnKey {
+    column: Column,
+    index: Option<usize>,
+}
+
+/// Key of [`group_records_by_with`]: the result of a closure.
+#[derive(Debug)]
+pub struct KeySelector<F>(F);
+
+mod private {
+    use std::io;
+
+    use ::csv::{Reader, StringRecord};
+
+    use super::RecordError;
+
+    pub trait RecordKey {
+        type Key;
+
+        /// Prepares the key before the first record is read.
+        fn resolve<R: io::Read>(&mut self, reader: &mut Reader<R>) -> Result<(), RecordError>;
+
+        fn key(&mut self, record: &StringRecord) -> Result<Self::Key, RecordError>;
+    }
+}
+
+use private::RecordKey;
+
+impl RecordKey for ColumnKey {
+    type Key = String;
+
+    fn resolve<R: io::Read>(&mut self, reader: &mut Reader<R>) -> Result<(), RecordError> {
+        let index = match &self.column {
+            Column::Index(index) => *index,
+            Column::Name(name) => {
+                let headers = reader.headers().map_err(RecordError::from)?;
+
+                headers
+                    .iter()
+                    .position(|header| header == name)
+                    .ok_or_else(|| RecordError {
+                        position: headers.position().cloned(),
+                        kind: RecordErrorKind::MissingColumn(self.column.clone()),
+                    })?
+            }
+        };
+
+        self.index = Some(index);
+        Ok(())
+    }
+
+    fn key(&mut self, record: &StringRecord) -> Result<String, RecordError> {
+        self.index
+            .and_then(|index| record.get(index))
+            .map(str::to_owned)
+            .ok_or_else(|| RecordError {
+                position: record.position().cloned(),
+                kind: RecordErrorKind::MissingColumn(self.column.clone()),
+            })
+    }
+}
+
+impl<K, F> RecordKey for KeySelector<F>
+where
+    F: FnMut(&StringRecord) -> K,
+{
+    type Key = K;
+
+    fn resolve<R: io::Read>(&mut self, _: &mut Reader<R>) -> Result<(), RecordError> {
+        Ok(())
+    }
+
+    fn key(&mut self, record: &StringRecord) -> Result<K, RecordError> {
+        Ok((self.0)(record))
+    }
+}
+
+/// Grouping over the records of a CSV reader. Unlike the in-memory groupings,
+/// its terminals are fallible: the first CSV error or record lacking the key
+/// column stops the aggregation and is returned.
+pub struct RecordGrouping<R, Rk> {
+    reader: Reader<R>,
+    key: Rk,
+}
+
+impl<R, Rk> RecordGrouping<R, Rk>
+where
+    R: io::Read,
+    Rk: RecordKey,
+    Rk::Key: Eq + Hash,
+{
+    pub fn aggregate<A, O>(self, mut operation: O) -> Result<HashMap<Rk::Key, A>, RecordError>
+    where
+        O: FnMut(&Rk::Key, Option<A>, StringRecord) -> A,
+    {
+        let Self {
+            mut reader,
+            mut key,
+        } = self;
+        let mut m = HashMap::new();
+
+        key.resolve(&mut reader)?;
+
+        for record in reader.into_records() {
+            let record = record?;
+
+            match m.entry(key.key(&record)?) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), record)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, record);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(m)
+    }
+
+    pub fn each_count(self) -> Result<HashMap<Rk::Key, usize>, RecordError> {
+        self.aggregate(|_, accumulator, _| accumulator.unwrap_or(0) + 1)
+    }
+
+    pub fn collect_groups(self) -> Result<HashMap<Rk::Key, Vec<StringRecord>>, RecordError> {
+        self.aggregate(|_, accumulator: Option<Vec<StringRecord>>, record| {
+            let mut accumulator = accumulator.unwrap_or_default();
+            accumulator.push(record);
+            accumulator
+        })
+    }
+}
+
+/// A CSV error or a record without the key column, with the position of the
+/// offending record where known.
+#[derive(Debug)]
+pub struct RecordError {
+    pub position: Option<Position>,
+    pub kind: RecordErrorKind,
+}
+
+#[derive(Debug)]
+pub enum RecordErrorKind {
+    Csv(::csv::Error),
+    MissingColumn(Column),
+}
+
+impl From<::csv::Error> for RecordError {
+    fn from(err: ::csv::Error) -> Self {
+        Self {
+            position: err.position().cloned(),
+            kind: RecordErrorKind::Csv(err),
+        }
+    }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `csv::Error` already reports its own position.
+        let column = match &self.kind {
+            RecordErrorKind::Csv(err) => return write!(f, "{err}"),
+            RecordErrorKind::MissingColumn(column) => column,
+        };
+
+        write!(f, "missing column {column}")?;
+
+        match &self.position {
+            Some(position) => write!(
+                f,
+                " (record {}, line {})",
+                position.record(),
+                position.line()
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for RecordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RecordErrorKind::Csv(err) => Some(err),
+            RecordErrorKind::MissingColumn(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, io::Cursor};
+
+    use ::csv::{Reader, ReaderBuilder};
+
+    use super::{group_records_by, group_records_by_with, Column, RecordErrorKind};
+
+    const ORDERS: &str = "\
+id,city,amount
+1,Taipei,120
+2,Osaka,80
+3,Taipei,45
+4,Tainan,300
+";
+
+    fn reader(csv: &'static str) -> Reader<Cursor<&'static str>> {
+        Reader::from_reader(Cursor::new(csv))
+    }
+
+    #[test]
+    fn test_group_records_by() {
+        let by_name = group_records_by(reader(ORDERS), "city")
+            .each_count()
+            .unwrap();
+        let by_index = group_records_by(reader(ORDERS), 1)
+            .collect_groups()
+            .unwrap();
+
+        assert_eq!(
+            by_name,
+            HashMap::from([
+                ("Taipei".to_owned(), 2),
+                ("Osaka".to_owned(), 1),
+                ("Tainan".to_owned(), 1)
+            ])
+        );
+        assert_eq!(by_index["Taipei"].len(), 2);
+        assert_eq!(&by_index["Taipei"][1][0], "3");
+    }
+
+    #[test]
+    fn test_group_records_by_with() {
+        let by_size = group_records_by_with(reader(ORDERS), |record| {
+            record[2].parse::<u32>().unwrap() >= 100
+        })
+        .each_count()
+        .unwrap();
+
+        assert_eq!(by_size, HashMap::from([(true, 2), (false, 2)]));
+    }
+
+    #[test]
+    fn test_group_records_by_missing_column() {
+        let err = group_records_by(reader(ORDERS), "country")
+            .each_count()
+            .unwrap_err();
+
+        assert!(matches!(
+            &err.kind,
+            RecordErrorKind::MissingColumn(Column::Name(name)) if name == "country"
+        ));
+        assert_eq!(err.position.as_ref().map(|p| p.line()), Some(1));
+
+        let short_row = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(Cursor::new("id,city\n1,Taipei\n2\n"));
+        let err = group_records_by(short_row, "city")
+            .each_count()
+            .unwrap_err();
+
+        assert_eq!(err.position.as_ref().map(|p| p.line()), Some(3));
+        assert_eq!(
+            err.to_string(),
+            "missing column \"city\" (record 2, line 3)"
+        );
+    }
+
+    #[test]
+    fn test_group_records_by_malformed_row() {
+        let malformed = "id,city,amount\n1,Taipei,120\n2,Osaka\n3,Taipei,45\n";
+        let err = group_records_by(reader(malformed), "city")
+            .each_count()
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RecordErrorKind::Csv(_)));
+        assert_eq!(err.position.as_ref().map(|p| p.line()), Some(3));
+    }
+}
@@ -0,0 +1,1588 @@
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::{
+        hash_map::{Entry, RandomState},
+        BTreeMap, HashMap,
+    },
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    ops::Add,
+};
+
+use crate::{lazy, maps};
+
+pub trait Grouping<T, K>
+where
+    K: Hash,
+    K: Eq,
+{
+    type Iter<'a>: Iterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn source_iterator(&self) -> Self::Iter<'_>;
+    fn key_of(&self, element: &T) -> K;
+
+    fn aggregate<R>(&self, operation: impl Fn(&K, Option<R>, &T) -> R) -> HashMap<K, R> {
+        self.aggregate_with_hasher(RandomState::new(), operation)
+    }
+
+    fn aggregate_with_hasher<R, S>(
+        &self,
+        hasher: S,
+        operation: impl Fn(&K, Option<R>, &T) -> R,
+    ) -> HashMap<K, R, S>
+    where
+        S: BuildHasher + Clone,
+    {
+        let mut m: HashMap<K, R, S> = HashMap::with_hasher(hasher);
+
+        for item in self.source_iterator() {
+            match m.entry(self.key_of(item)) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), item)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, item);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        m
+    }
+
+    /// Like [`aggregate`](Self::aggregate), but stops at the first error. The
+    /// partially built map is discarded and the grouping itself is untouched,
+    /// so it can be aggregated again.
+    fn try_aggregate<R, E>(
+        &self,
+        operation: impl Fn(&K, Option<R>, &T) -> Result<R, E>,
+    ) -> Result<HashMap<K, R>, E> {
+        let mut m = HashMap::new();
+
+        for item in self.source_iterator() {
+            match m.entry(self.key_of(item)) {
+                Entry::Occupied(entry) => {
+                    maps::try_update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), item)
+                    })?;
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, item)?;
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(m)
+    }
+
+    fn aggregate_indexed<R>(
+        &self,
+        operation: impl Fn(usize, &K, Option<R>, &T) -> R,
+    ) -> HashMap<K, R> {
+        let index = Cell::new(0);
+
+        self.aggregate(|key, accumulator, item| {
+            let current = index.replace(index.get() + 1);
+
+            operation(current, key, accumulator, item)
+        })
+    }
+
+    fn indices_by(&self) -> HashMap<K, Vec<usize>> {
+        self.aggregate_indexed(|index, _, accumulator: Option<Vec<usize>>, _| {
+            let mut accumulator = accumulator.unwrap_or_default();
+            accumulator.push(index);
+            accumulator
+        })
+    }
+
+    fn fold_with_key<R>(
+        &self,
+        initial_value_selector: impl Fn(&K, &T) -> R,
+        operation: impl Fn(&K, R, &T) -> R,
+    ) -> HashMap<K, R> {
+        self.aggregate(|key, accumulator, item| {
+            operation(
+                key,
+                accumulator.unwrap_or(initial_value_selector(key, item)),
+                item,
+            )
+        })
+    }
+
+    fn try_fold_with_key<R, E>(
+        &self,
+        initial_value_selector: impl Fn(&K, &T) -> R,
+        operation: impl Fn(&K, R, &T) -> Result<R, E>,
+    ) -> Result<HashMap<K, R>, E> {
+        self.try_aggregate(|key, accumulator, item| {
+            operation(
+                key,
+                accumulator.unwrap_or_else(|| initial_value_selector(key, item)),
+                item,
+            )
+        })
+    }
+
+    fn fold<R>(&self, initial_value: R, operation: impl Fn(R, &T) -> R) -> HashMap<K, R>
+    where
+        R: Clone,
+    {
+        self.fold_with_hasher(RandomState::new(), initial_value, operation)
+    }
+
+    fn fold_with_hasher<R, S>(
+        &self,
+        hasher: S,
+        initial_value: R,
+        operation: impl Fn(R, &T) -> R,
+    ) -> HashMap<K, R, S>
+    where
+        R: Clone,
+        S: BuildHasher + Clone,
+    {
+        self.aggregate_with_hasher(hasher, |_, accumulator, item| {
+            operation(accumulator.unwrap_or(initial_value.clone()), item)
+        })
+    }
+
+    fn reduce_with_key<R>(&self, operation: impl Fn(&K, R, &T) -> R) -> HashMap<K, R>
+    where
+        T: Clone,
+        T: Into<R>,
+    {
+        self.aggregate(|key, accumulator, item| {
+            if let Some(accumulator) = accumulator {
+                operation(key, accumulator, item)
+            } else {
+                item.clone().into()
+            }
+        })
+    }
+
+    fn each_count(&self) -> HashMap<K, usize> {
+        self.each_count_with_hasher(RandomState::new())
+    }
+
+    fn each_count_with_hasher<S>(&self, hasher: S) -> HashMap<K, usize, S>
+    where
+        S: BuildHasher + Clone,
+    {
+        self.fold_with_hasher(hasher, 0, |accumulator, _| accumulator + 1)
+    }
+
+    /// The smallest projected value per group. Values incomparable with the
+    /// current minimum (`partial_cmp` returns `None`, e.g. NaN) are skipped.
+    fn min_of<N>(&self, selector: impl Fn(&T) -> N) -> HashMap<K, N>
+    where
+        N: PartialOrd,
+    {
+        self.aggregate(|_, accumulator, item| {
+            lazy::keep_extreme(accumulator, selector(item), Ordering::Less)
+        })
+    }
+
+    /// The largest projected value per group. Values incomparable with the
+    /// current maximum (`partial_cmp` returns `None`, e.g. NaN) are skipped.
+    fn max_of<N>(&self, selector: impl Fn(&T) -> N) -> HashMap<K, N>
+    where
+        N: PartialOrd,
+    {
+        self.aggregate(|_, accumulator, item| {
+            lazy::keep_extreme(accumulator, selector(item), Ordering::Greater)
+        })
+    }
+
+    fn sum_of<N>(&self, selector: impl Fn(&T) -> N) -> HashMap<K, N>
+    where
+        N: Add<Output = N>,
+    {
+        self.aggregate(|_, accumulator, item| {
+            let value = selector(item);
+
+            match accumulator {
+                Some(sum) => sum + value,
+                None => value,
+            }
+        })
+    }
+
+    fn average_of<N>(&self, selector: impl Fn(&T) -> N) -> HashMap<K, f64>
+    where
+        N: Into<f64>,
+    {
+        self.aggregate(|_, accumulator: Option<(f64, usize)>, item| {
+            let (sum, count) = accumulator.unwrap_or_default();
+
+            (sum + selector(item).into(), count + 1)
+        })
+        .into_iter()
+        .map(|(key, (sum, count))| (key, sum / count as f64))
+        .collect()
+    }
+
+    fn aggregate_sorted<R>(&self, operation: impl Fn(&K, Option<R>, &T) -> R) -> BTreeMap<K, R>
+    where
+        K: Ord,
+    {
+        self.aggregate(operation).into_iter().collect()
+    }
+
+    /// Counts the elements of each group, in ascending key order.
+    ///
+    /// ```
+    /// use metamorphosis::eager::{Grouping, IntoGrouping};
+    ///
+    /// let words = vec!["one", "two", "three", "four", "five", "six"];
+    /// let counts = words
+    ///     .grouping_by(|w| w.chars().next().unwrap())
+    ///     .each_count_sorted();
+    ///
+    /// assert_eq!(format!("{:?}", counts), "{'f': 2, 'o': 1, 's': 1, 't': 2}");
+    /// ```
+    fn each_count_sorted(&self) -> BTreeMap<K, usize>
+    where
+        K: Ord,
+    {
+        self.each_count().into_iter().collect()
+    }
+
+    fn items_for<'a>(&'a self, key: &K) -> Vec<&'a T>
+    where
+        T: 'a,
+    {
+        self.source_iterator()
+            .filter(|item| self.key_of(item) == *key)
+            .collect()
+    }
+
+    fn count_of(&self, key: &K) -> usize {
+        self.source_iterator()
+            .filter(|item| self.key_of(item) == *key)
+            .count()
+    }
+
+    fn then_grouping_by<K2, Ks2>(&self, second_key: Ks2) -> NestedGrouping<'_, Self, T, K, K2, Ks2>
+    where
+        K2: Eq,
+        K2: Hash,
+        Ks2: Fn(&T) -> K2,
+    {
+        NestedGrouping {
+            grouping: self,
+            second_key,
+            keys: PhantomData,
+            items: PhantomData,
+        }
+    }
+
+    fn each_count_to<'m>(
+        &self,
+        destination: &'m mut HashMap<K, usize>,
+    ) -> &'m mut HashMap<K, usize> {
+        for item in self.source_iterator() {
+            *destination.entry(self.key_of(item)).or_insert(0) += 1;
+        }
+
+        destination
+    }
+}
+
+/// Two-level grouping produced by [`Grouping::then_grouping_by`]. Every
+/// terminal computes both keys exactly once per element.
+pub struct NestedGrouping<'g, G, T, K, K2, Ks2>
+where
+    G: ?Sized,
+{
+    grouping: &'g G,
+    second_key: Ks2,
+    keys: PhantomData<(K, K2)>,
+    items: PhantomData<fn(&T)>,
+}
+
+impl<'g, G, T, K, K2, Ks2> NestedGrouping<'g, G, T, K, K2, Ks2>
+where
+    G: Grouping<T, K> + ?Sized,
+    T: 'g,
+    K: Eq,
+    K: Hash,
+    K2: Eq,
+    K2: Hash,
+    Ks2: Fn(&T) -> K2,
+{
+    fn aggregate<R>(
+        &self,
+        operation: impl Fn(Option<R>, &'g T) -> R,
+    ) -> HashMap<K, HashMap<K2, R>> {
+        let mut m: HashMap<K, HashMap<K2, Option<R>>> = HashMap::new();
+
+        for item in self.grouping.source_iterator() {
+            let slot = m
+                .entry(self.grouping.key_of(item))
+                .or_default()
+                .entry((self.second_key)(item))
+                .or_insert(None);
+
+            *slot = Some(operation(slot.take(), item));
+        }
+
+        m.into_iter()
+            .map(|(key, inner)| {
+                let inner = inner
+                    .into_iter()
+                    .filter_map(|(key, value)| value.map(|value| (key, value)))
+                    .collect();
+
+                (key, inner)
+            })
+            .collect()
+    }
+
+    pub fn to_group_map(&self) -> HashMap<K, HashMap<K2, Vec<&'g T>>> {
+        self.aggregate(|accumulator: Option<Vec<_>>, item| {
+            let mut accumulator = accumulator.unwrap_or_default();
+            accumulator.push(item);
+            accumulator
+        })
+    }
+
+    pub fn each_count(&self) -> HashMap<K, HashMap<K2, usize>> {
+        self.fold(0, |accumulator, _| accumulator + 1)
+    }
+
+    pub fn fold<R>(
+        &self,
+        initial_value: R,
+        operation: impl Fn(R, &T) -> R,
+    ) -> HashMap<K, HashMap<K2, R>>
+    where
+        R: Clone,
+    {
+        self.aggregate(|accumulator, item| {
+            operation(accumulator.unwrap_or(initial_value.clone()), item)
+        })
+    }
+}
+
+pub type BoxedKeySelector<'ks, T, K> = Box<dyn Fn(&T) -> K + 'ks>;
+
+pub struct GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    Ks: Fn(&T) -> K,
+{
+    raw: Vec<T>,
+    key_selector: Ks,
+}
+
+impl<T, K, Ks> GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    Ks: Fn(&T) -> K,
+{
+    pub fn boxed<'ks>(self) -> GroupingImpl<T, K, BoxedKeySelector<'ks, T, K>>
+    where
+        Ks: 'ks,
+    {
+        GroupingImpl {
+            raw: self.raw,
+            key_selector: Box::new(self.key_selector),
+        }
+    }
+
+    pub fn retain(&mut self, mut predicate: impl FnMut(&K, &T) -> bool) {
+        let key_selector = &self.key_selector;
+
+        self.raw.retain(|item| predicate(&key_selector(item), item));
+    }
+
+    pub fn retain_keys(&mut self, mut predicate: impl FnMut(&K) -> bool) {
+        self.retain(|key, _| predicate(key));
+    }
+
+    pub fn filter(mut self, predicate: impl FnMut(&K, &T) -> bool) -> Self {
+        self.retain(predicate);
+        self
+    }
+
+    /// Moves every element through `mapping` into a new grouping keyed by
+    /// `key_selector`, which sees the mapped values.
+    pub fn map_items<U, K2, Ks2>(
+        self,
+        mapping: impl FnMut(T) -> U,
+        key_selector: Ks2,
+    ) -> GroupingImpl<U, K2, Ks2>
+    where
+        K2: Eq,
+        K2: Hash,
+        Ks2: Fn(&U) -> K2,
+    {
+        GroupingImpl {
+            raw: self.raw.into_iter().map(mapping).collect(),
+            key_selector,
+        }
+    }
+
+    pub fn aggregate_into<R>(self, operation: impl FnMut(&K, Option<R>, T) -> R) -> HashMap<K, R> {
+        lazy::Grouping::new(self.raw.into_iter(), self.key_selector).aggregate(operation)
+    }
+
+    pub fn into_group_map(self) -> HashMap<K, Vec<T>> {
+        self.aggregate_into(push_item)
+    }
+
+    /// Like [`into_group_map`](Self::into_group_map), with every group stably
+    /// sorted by `compare`.
+    pub fn into_sorted_group_map_by(
+        self,
+        mut compare: impl FnMut(&T, &T) -> Ordering,
+    ) -> HashMap<K, Vec<T>> {
+        let mut groups = self.into_group_map();
+
+        for group in groups.values_mut() {
+            group.sort_by(&mut compare);
+        }
+
+        groups
+    }
+
+    pub fn into_sorted_group_map_by_key<B>(
+        self,
+        mut key_fn: impl FnMut(&T) -> B,
+    ) -> HashMap<K, Vec<T>>
+    where
+        B: Ord,
+    {
+        self.into_sorted_group_map_by(|a, b| key_fn(a).cmp(&key_fn(b)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.raw.push(item);
+    }
+
+    /// Removes every element while keeping the buffer's capacity and the key
+    /// selector, so the grouping can be refilled and reused.
+    pub fn drain(&mut self) -> std::vec::Drain<'_, T> {
+        self.raw.drain(..)
+    }
+
+    pub fn drain_group_map(&mut self) -> HashMap<K, Vec<T>> {
+        lazy::Grouping::new(self.raw.drain(..), &self.key_selector).aggregate(push_item)
+    }
+}
+
+impl<T, K, Ks> GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    K: Ord,
+    K: Display,
+    T: Display,
+    Ks: Fn(&T) -> K,
+{
+    /// Renders the groups as a JSON object with sorted keys. Keys and items
+    /// are always emitted as strings of their `Display` output, even when that
+    /// output looks like a number.
+    pub fn to_json_string(&self) -> String {
+        let mut groups: BTreeMap<K, Vec<&T>> = BTreeMap::new();
+
+        for item in self.raw.iter() {
+            groups.entry(self.key_of(item)).or_default().push(item);
+        }
+
+        let mut json = String::from("{");
+
+        for (index, (key, items)) in groups.into_iter().enumerate() {
+            if index > 0 {
+                json.push_str(", ");
+            }
+
+            push_json_string(&mut json, &key);
+            json.push_str(": [");
+
+            for (index, item) in items.into_iter().enumerate() {
+                if index > 0 {
+                    json.push_str(", ");
+                }
+
+                push_json_string(&mut json, item);
+            }
+
+            json.push(']');
+        }
+
+        json.push('}');
+        json
+    }
+}
+
+fn push_json_string(json: &mut String, value: &impl Display) {
+    json.push('"');
+
+    for c in value.to_string().chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+fn push_item<K, T>(_: &K, accumulator: Option<Vec<T>>, item: T) -> Vec<T> {
+    let mut accumulator = accumulator.unwrap_or_default();
+    accumulator.push(item);
+    accumulator
+}
+
+impl<T, K, Ks> Extend<T> for GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    Ks: Fn(&T) -> K,
+{
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        self.raw.extend(iter);
+    }
+}
+
+impl<T, K, Ks> Grouping<T, K> for GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    Ks: Fn(&T) -> K,
+{
+    type Iter<'src>
+        = std::slice::Iter<'src, T>
+    where
+        Self: 'src,
+        T: 'src;
+
+    fn source_iterator(&self) -> Self::Iter<'_> {
+        self.raw.iter()
+    }
+
+    fn key_of(&self, element: &T) -> K {
+        (self.key_selector)(element)
+    }
+}
+
+impl<T, K, Ks> Display for GroupingImpl<T, K, Ks>
+where
+    K: Eq,
+    K: Hash,
+    Ks: Fn(&T) -> K,
+    T: Display,
+    K: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Groups are numbered in first-seen order and the elements are visited
+        // through one index array sorted by group, so the number of
+        // allocations does not depend on the number of groups.
+        let mut ordinals: HashMap<K, usize> = HashMap::new();
+        let group_of = self
+            .raw
+            .iter()
+            .map(|item| {
+                let next = ordinals.len();
+
+                *ordinals.entry(self.key_of(item)).or_insert(next)
+            })
+            .collect::<Vec<_>>();
+        let mut keys = (0..ordinals.len()).map(|_| None).collect::<Vec<_>>();
+
+        for (key, ordinal) in ordinals {
+            keys[ordinal] = Some(key);
+        }
+
+        let mut order = (0..self.raw.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| group_of[index]);
+
+        write!(f, "{{")?;
+
+        for (position, &index) in order.iter().enumerate() {
+            let group = group_of[index];
+            let starts_group = position == 0 || group_of[order[position - 1]] != group;
+
+            if starts_group {
+                if position > 0 {
+                    write!(f, "], ")?;
+                }
+
+                if let Some(key) = &keys[group] {
+                    write!(f, "{}=[", key)?;
+                }
+            } else {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{}", self.raw[index])?;
+        }
+
+        if !order.is_empty() {
+            write!(f, "]")?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+pub trait IntoGrouping<T, K>
+where
+    K: Eq,
+    K: Hash,
+{
+    fn grouping_by<Ks>(self, key_selector: Ks) -> GroupingImpl<T, K, Ks>
+    where
+        Ks: Fn(&T) -> K;
+
+    /// Computes each element's key and value up front and drops the element,
+    /// so the grouping only keeps `(key, value)` pairs.
+    fn grouping_by_with<Ks, Vs, V>(
+        self,
+        key_selector: Ks,
+        value_selector: Vs,
+    ) -> ValueGrouping<K, V>
+    where
+        Ks: Fn(&T) -> K,
+        Vs: Fn(T) -> V,
+        K: Clone;
+}
+
+/// Grouping built by [`IntoGrouping::grouping_by_with`]. Each value is
+/// stored next to the key computed from its original element, so terminals
+/// see `&V` and no key selector runs again. Values do not carry their keys,
+/// so this is not a [`Grouping`]; it offers the common terminals itself.
+pub struct ValueGrouping<K, V> {
+    pairs: Vec<(K, V)>,
+}
+
+impl<K, V> ValueGrouping<K, V>
+where
+    K: Eq,
+    K: Hash,
+    K: Clone,
+{
+    /// Every value with its key, in source order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.pairs.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn aggregate<R>(&self, operation: impl Fn(&K, Option<R>, &V) -> R) -> HashMap<K, R> {
+        let mut m = HashMap::new();
+
+        for (key, value) in &self.pairs {
+            match m.entry(key.clone()) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), value)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, value);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        m
+    }
+
+    /// Like [`aggregate`](Self::aggregate), but stops at the first error.
+    pub fn try_aggregate<R, E>(
+        &self,
+        operation: impl Fn(&K, Option<R>, &V) -> Result<R, E>,
+    ) -> Result<HashMap<K, R>, E> {
+        let mut m = HashMap::new();
+
+        for (key, value) in &self.pairs {
+            match m.entry(key.clone()) {
+                Entry::Occupied(entry) => {
+                    maps::try_update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), value)
+                    })?;
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, value)?;
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        Ok(m)
+    }
+
+    pub fn fold<R>(&self, initial_value: R, operation: impl Fn(R, &V) -> R) -> HashMap<K, R>
+    where
+        R: Clone,
+    {
+        self.aggregate(|_, accumulator, value| {
+            operation(accumulator.unwrap_or(initial_value.clone()), value)
+        })
+    }
+
+    pub fn each_count(&self) -> HashMap<K, usize> {
+        let mut counts = HashMap::new();
+
+        for (key, _) in &self.pairs {
+            *counts.entry(key.clone()).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
+    pub fn sum_of<N>(&self, selector: impl Fn(&V) -> N) -> HashMap<K, N>
+    where
+        N: Add<Output = N>,
+    {
+        self.aggregate(|_, accumulator, value| match accumulator {
+            Some(sum) => sum + selector(value),
+            None => selector(value),
+        })
+    }
+
+    pub fn items_for(&self, key: &K) -> Vec<&V> {
+        self.pairs
+            .iter()
+            .filter(|(other, _)| other == key)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn count_of(&self, key: &K) -> usize {
+        self.pairs.iter().filter(|(other, _)| other == key).count()
+    }
+}
+
+impl<T, K> IntoGrouping<T, K> for Vec<T>
+where
+    K: Eq,
+    K: Hash,
+{
+    fn grouping_by<Ks>(self, key_selector: Ks) -> GroupingImpl<T, K, Ks>
+    where
+        Ks: Fn(&T) -> K,
+    {
+        GroupingImpl {
+            raw: self,
+            key_selector,
+        }
+    }
+
+    fn grouping_by_with<Ks, Vs, V>(
+        self,
+        key_selector: Ks,
+        value_selector: Vs,
+    ) -> ValueGrouping<K, V>
+    where
+        Ks: Fn(&T) -> K,
+        Vs: Fn(T) -> V,
+        K: Clone,
+    {
+        ValueGrouping {
+            pairs: self
+                .into_iter()
+                .map(|item| (key_selector(&item), value_selector(item)))
+                .collect(),
+        }
+    }
+}
+
+/// Groups a borrowed slice without cloning its elements: `grouping_by`
+/// allocates a vector of `&'a T`, one reference per element. Key selectors
+/// can return data borrowed from them, e.g. `|l| l.host.as_str()`.
+impl<'a, T, K> IntoGrouping<&'a T, K> for &'a [T]
+where
+    K: Eq,
+    K: Hash,
+{
+    fn grouping_by<Ks>(self, key_selector: Ks) -> GroupingImpl<&'a T, K, Ks>
+    where
+        Ks: Fn(&&'a T) -> K,
+    {
+        GroupingImpl {
+            raw: self.iter().collect(),
+            key_selector,
+        }
+    }
+
+    fn grouping_by_with<Ks, Vs, V>(
+        self,
+        key_selector: Ks,
+        value_selector: Vs,
+    ) -> ValueGrouping<K, V>
+    where
+        Ks: Fn(&&'a T) -> K,
+        Vs: Fn(&'a T) -> V,
+        K: Clone,
+    {
+        ValueGrouping {
+            pairs: self
+                .iter()
+                .map(|item| (key_selector(&item), value_selector(item)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        collections::{hash_map::DefaultHasher, HashMap},
+        hash::{BuildHasher, Hash, Hasher},
+    };
+
+    use super::{Grouping, IntoGrouping};
+
+    #[test]
+    fn test_each_count() {
+        let words = "one two three four five six seven eight nine ten"
+            .split(" ")
+            .collect::<Vec<_>>();
+        let freq_by_first_char = words
+            .grouping_by(|s| s.chars().next().unwrap())
+            .each_count();
+
+        // {o=1, t=3, f=2, s=2, e=1, n=1}
+        assert_eq!(
+            freq_by_first_char,
+            HashMap::from([('o', 1), ('t', 3), ('f', 2), ('s', 2), ('e', 1), ('n', 1)])
+        )
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let numbers = (3..=9).collect::<Vec<usize>>();
+        let aggregated =
+            numbers
+                .grouping_by(|i| *i % 3)
+                .aggregate(|key, accumulator: Option<String>, item| {
+                    if let Some(mut accumulator) = accumulator {
+                        accumulator.push_str(&format!("-{}", item));
+                        accumulator
+                    } else {
+                        format!("{}:{}", key, item)
+                    }
+                });
+
+        assert_eq!(
+            aggregated,
+            HashMap::from([
+                (0, "0:3-6-9".to_string()),
+                (1, "1:4-7".to_string()),
+                (2, "2:5-8".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fold_with_key() {
+        let fruits = vec![
+            "cherry",
+            "blueberry",
+            "citrus",
+            "apple",
+            "apricot",
+            "banana",
+            "coconut",
+        ];
+        let even_fruits = fruits
+            .grouping_by(|f| f.chars().next().unwrap())
+            .fold_with_key(
+                |&k, _| (k, vec![]),
+                |_, mut accumulator, item| {
+                    if item.len() % 2 == 0 {
+                        accumulator.1.push(item.to_string());
+                    }
+
+                    accumulator
+                },
+            );
+
+        assert_eq!(
+            even_fruits,
+            HashMap::from([
+                ('a', ('a', vec![])),
+                ('b', ('b', vec!["banana".to_string()])),
+                ('c', ('c', vec!["cherry".to_string(), "citrus".to_string()]))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        let fruits = vec![
+            "apple",
+            "apricot",
+            "banana",
+            "blueberry",
+            "cherry",
+            "coconut",
+        ];
+        let even_fruits = fruits.grouping_by(|f| f.chars().next().unwrap()).fold(
+            vec![],
+            |mut accumulator, item| {
+                if item.len() % 2 == 0 {
+                    accumulator.push(item.to_string());
+                }
+
+                accumulator
+            },
+        );
+
+        assert_eq!(
+            even_fruits,
+            HashMap::from([
+                ('a', vec![]),
+                ('b', vec!["banana".to_string()]),
+                ('c', vec!["cherry".to_string()])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_reduce() {
+        let animals = vec!["raccoon", "reindeer", "cow", "camel", "giraffe", "goat"];
+        let is_vowel = |&c: &char| c == 'a' || c == 'e' || c == 'i' || c == 'o' || c == 'u';
+        let max_vowels = animals
+            .grouping_by(|a| a.chars().next().unwrap())
+            .reduce_with_key(|_, accumulator: &str, item| {
+                let acc_vowels = accumulator.chars().filter(is_vowel).count();
+                let item_vowels = item.chars().filter(is_vowel).count();
+
+                match acc_vowels.cmp(&item_vowels) {
+                    std::cmp::Ordering::Less => item,
+                    std::cmp::Ordering::Equal | std::cmp::Ordering::Greater => accumulator,
+                }
+            });
+
+        assert_eq!(
+            max_vowels,
+            HashMap::from([('r', "reindeer"), ('c', "camel"), ('g', "giraffe")])
+        );
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut numbers = (1..=6).collect::<Vec<usize>>().grouping_by(|i| *i % 2);
+
+        numbers.retain(|&key, &item| key == 0 || item > 3);
+
+        assert_eq!(numbers.each_count(), HashMap::from([(0, 3), (1, 1)]));
+
+        numbers.retain_keys(|&key| key == 0);
+
+        assert_eq!(numbers.each_count(), HashMap::from([(0, 3)]));
+        assert_eq!(
+            numbers.fold(0, |accumulator, item| accumulator + item),
+            HashMap::from([(0, 12)])
+        );
+        assert_eq!(numbers.to_string(), "{0=[2, 4, 6]}");
+    }
+
+    #[test]
+    fn test_grouping_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        let numbers = vec![1, 2, 3, 4, 5].grouping_by(|i: &i32| *i % 2);
+
+        assert_send(&numbers);
+
+        let counts = std::thread::spawn(move || numbers.each_count())
+            .join()
+            .unwrap();
+
+        assert_eq!(counts, HashMap::from([(0, 2), (1, 3)]));
+    }
+
+    #[test]
+    fn test_boxed() {
+        let groupings = [
+            vec![1, 2, 3].grouping_by(|i: &i32| *i % 2).boxed(),
+            vec![1, 2, 3].grouping_by(|_: &i32| 0).boxed(),
+        ];
+
+        assert_eq!(groupings[0].each_count(), HashMap::from([(0, 1), (1, 2)]));
+        assert_eq!(groupings[1].each_count(), HashMap::from([(0, 3)]));
+    }
+
+    #[test]
+    fn test_aggregate_hashes_once_per_element() {
+        thread_local! {
+            static HASHES: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(PartialEq, Eq)]
+        struct CountingKey(char);
+
+        impl Hash for CountingKey {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                HASHES.with(|hashes| hashes.set(hashes.get() + 1));
+                self.0.hash(state);
+            }
+        }
+
+        let fruits = vec![
+            "cherry",
+            "blueberry",
+            "citrus",
+            "apple",
+            "apricot",
+            "banana",
+            "coconut",
+        ];
+        let len = fruits.len();
+        let counts = fruits
+            .grouping_by(|f| CountingKey(f.chars().next().unwrap()))
+            .each_count();
+        let hashes = HASHES.with(Cell::get);
+
+        // One entry lookup per element; accumulators are updated in place.
+        assert_eq!(counts.len(), 3);
+        assert_eq!(hashes, len);
+    }
+
+    #[test]
+    fn test_into_group_map() {
+        #[derive(Debug, PartialEq)]
+        struct Event {
+            kind: Box<str>,
+            id: usize,
+        }
+
+        let event = |kind: &str, id| Event {
+            kind: kind.into(),
+            id,
+        };
+        let events = vec![event("click", 0), event("view", 1), event("click", 2)];
+        let by_kind = events.grouping_by(|e| e.kind.clone()).into_group_map();
+
+        assert_eq!(
+            by_kind,
+            HashMap::from([
+                ("click".into(), vec![event("click", 0), event("click", 2)]),
+                ("view".into(), vec![event("view", 1)])
+            ])
+        );
+
+        let events = vec![event("click", 0), event("view", 1), event("click", 2)];
+        let ids = events.grouping_by(|e| e.kind.len()).aggregate_into(
+            |_, accumulator: Option<Vec<Event>>, item| {
+                let mut accumulator = accumulator.unwrap_or_default();
+                accumulator.push(item);
+                accumulator
+            },
+        );
+
+        assert_eq!(ids[&5].iter().map(|e| e.id).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(ids[&4].iter().map(|e| e.id).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_into_group_map_preserves_len() {
+        let words = "the quick brown fox jumps over the lazy dog"
+            .split(' ')
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let grouping = words.grouping_by(|word| word.len());
+        let len = grouping.len();
+        let by_len = grouping.into_group_map();
+
+        assert_eq!(by_len.values().map(Vec::len).sum::<usize>(), len);
+        assert_eq!(by_len[&3], ["the", "fox", "the", "dog"]);
+        assert_eq!(by_len[&5], ["quick", "brown", "jumps"]);
+    }
+
+    #[test]
+    fn test_into_sorted_group_map() {
+        let transactions = vec![
+            ("alice", 3, "rent"),
+            ("bob", 2, "food"),
+            ("alice", 1, "food"),
+            ("bob", 2, "fuel"),
+            ("alice", 3, "gym"),
+            ("bob", 1, "rent"),
+            ("alice", 1, "taxi"),
+        ];
+        let by_date = transactions
+            .clone()
+            .grouping_by(|(account, _, _)| *account)
+            .into_sorted_group_map_by_key(|(_, date, _)| *date);
+        let memos = |groups: &HashMap<&str, Vec<(&str, i32, &'static str)>>, account| {
+            groups[account]
+                .iter()
+                .map(|(_, _, memo)| *memo)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(memos(&by_date, "alice"), ["food", "taxi", "rent", "gym"]);
+        assert_eq!(memos(&by_date, "bob"), ["rent", "food", "fuel"]);
+
+        let by_date_desc = transactions
+            .grouping_by(|(account, _, _)| *account)
+            .into_sorted_group_map_by(|(_, a, _), (_, b, _)| b.cmp(a));
+
+        assert_eq!(
+            memos(&by_date_desc, "alice"),
+            ["rent", "gym", "food", "taxi"]
+        );
+        assert_eq!(memos(&by_date_desc, "bob"), ["food", "fuel", "rent"]);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut events = Vec::with_capacity(8).grouping_by(|i: &u32| *i % 2);
+
+        events.extend([1, 2, 3]);
+        events.push(5);
+
+        assert_eq!(
+            events.drain_group_map(),
+            HashMap::from([(0, vec![2]), (1, vec![1, 3, 5])])
+        );
+        assert_eq!(events.len(), 0);
+        assert!(events.each_count().is_empty());
+
+        events.extend([4, 6, 7]);
+
+        assert_eq!(events.each_count(), HashMap::from([(0, 2), (1, 1)]));
+        assert_eq!(events.drain().collect::<Vec<_>>(), [4, 6, 7]);
+        assert!(events.is_empty());
+        assert!(events.drain_group_map().is_empty());
+        assert!(events.raw.capacity() >= 8);
+    }
+
+    #[test]
+    fn test_each_count_to() {
+        let first_file = vec!["apple", "banana", "cherry"].grouping_by(|f| f.len());
+        let second_file = vec!["kiwi", "grape", "orange"].grouping_by(|f| f.len());
+        let mut counts = HashMap::new();
+
+        first_file.each_count_to(&mut counts);
+        second_file.each_count_to(&mut counts);
+
+        assert_eq!(counts, HashMap::from([(4, 1), (5, 2), (6, 3)]));
+
+        first_file.each_count_to(&mut counts).remove(&4);
+
+        assert_eq!(counts, HashMap::from([(5, 3), (6, 5)]));
+    }
+
+    #[test]
+    fn test_indices_by() {
+        let codes = vec![500, 404, 500, 200, 404, 500];
+        let grouping = codes.grouping_by(|c| *c);
+        let positions = grouping.indices_by();
+
+        assert_eq!(
+            positions,
+            HashMap::from([(500, vec![0, 2, 5]), (404, vec![1, 4]), (200, vec![3])])
+        );
+
+        for (code, indices) in positions {
+            assert!(indices.iter().all(|&index| grouping.raw[index] == code));
+        }
+
+        let first_and_last = grouping.aggregate_indexed(|index, _, accumulator, _| {
+            accumulator.map_or((index, index), |(first, _)| (first, index))
+        });
+
+        assert_eq!(
+            first_and_last,
+            HashMap::from([(500, (0, 5)), (404, (1, 4)), (200, (3, 3))])
+        );
+    }
+
+    #[test]
+    fn test_custom_source_iterator() {
+        struct EveryOther(Vec<u32>);
+
+        impl Grouping<u32, bool> for EveryOther {
+            type Iter<'a> = std::iter::StepBy<std::slice::Iter<'a, u32>>;
+
+            fn source_iterator(&self) -> Self::Iter<'_> {
+                self.0.iter().step_by(2)
+            }
+
+            fn key_of(&self, element: &u32) -> bool {
+                *element > 2
+            }
+        }
+
+        let grouping = EveryOther(vec![1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(
+            grouping.each_count(),
+            HashMap::from([(false, 1), (true, 2)])
+        );
+        assert_eq!(
+            grouping.fold(0, |accumulator, item| accumulator + item),
+            HashMap::from([(false, 1), (true, 8)])
+        );
+    }
+
+    #[test]
+    fn test_to_json_string() {
+        let quotes = vec![
+            "say \"hi\"".to_string(),
+            "tab\there".to_string(),
+            "say \"bye\"".to_string(),
+            "back\\slash\u{1}".to_string(),
+            "42".to_string(),
+        ];
+        let json = quotes
+            .grouping_by(|q| q.split(' ').next().unwrap().to_string())
+            .to_json_string();
+
+        assert_eq!(
+            json,
+            r#"{"42": ["42"], "back\\slash\u0001": ["back\\slash\u0001"], "say": ["say \"hi\"", "say \"bye\""], "tab\there": ["tab\there"]}"#
+        );
+        assert_eq!(
+            vec!["a\"b"].grouping_by(|s| s.to_string()).to_json_string(),
+            r#"{"a\"b": ["a\"b"]}"#
+        );
+        assert_eq!(Vec::<u8>::new().grouping_by(|i| *i).to_json_string(), "{}");
+    }
+
+    #[test]
+    fn test_numeric_terminals() {
+        let numbers = (1..=10).collect::<Vec<u32>>().grouping_by(|i| *i % 3);
+
+        assert_eq!(
+            numbers.min_of(|i| *i),
+            HashMap::from([(0, 3), (1, 1), (2, 2)])
+        );
+        assert_eq!(
+            numbers.max_of(|i| *i),
+            HashMap::from([(0, 9), (1, 10), (2, 8)])
+        );
+        assert_eq!(
+            numbers.sum_of(|i| *i),
+            HashMap::from([(0, 18), (1, 22), (2, 15)])
+        );
+        assert_eq!(
+            numbers.average_of(|i| *i),
+            HashMap::from([(0, 6.0), (1, 5.5), (2, 5.0)])
+        );
+
+        let ties = vec![(0, 'a'), (1, 'b'), (1, 'c'), (0, 'd')].grouping_by(|_| ());
+
+        assert_eq!(
+            ties.min_of(|&(score, name)| (score, name)),
+            HashMap::from([((), (0, 'a'))])
+        );
+        assert_eq!(ties.max_of(|&(score, _)| score), HashMap::from([((), 1)]));
+
+        let readings = vec![f64::NAN, 2.0, f64::NAN, 1.0, 3.0].grouping_by(|_| ());
+
+        assert_eq!(readings.min_of(|r| *r), HashMap::from([((), 1.0)]));
+        assert_eq!(readings.max_of(|r| *r), HashMap::from([((), 3.0)]));
+    }
+
+    #[test]
+    fn test_each_count_sorted() {
+        let words = "one two three four five six seven eight nine ten"
+            .split(' ')
+            .collect::<Vec<_>>()
+            .grouping_by(|s| s.chars().next().unwrap());
+        let counts = words.each_count_sorted();
+
+        assert_eq!(
+            counts.keys().copied().collect::<Vec<_>>(),
+            ['e', 'f', 'n', 'o', 's', 't']
+        );
+        assert_eq!(
+            counts.values().copied().collect::<Vec<_>>(),
+            [1, 2, 1, 1, 2, 3]
+        );
+        assert_eq!(
+            words
+                .aggregate_sorted(|_, accumulator, item| accumulator.unwrap_or(0) + item.len())
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [('e', 5), ('f', 8), ('n', 4), ('o', 3), ('s', 8), ('t', 11)]
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct FixedState;
+
+    impl BuildHasher for FixedState {
+        type Hasher = DefaultHasher;
+
+        fn build_hasher(&self) -> DefaultHasher {
+            DefaultHasher::new()
+        }
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let grouping = vec![1, 2, 3, 4, 5, 6, 7].grouping_by(|i| i % 3);
+        let counts: HashMap<i32, usize, FixedState> = grouping.each_count_with_hasher(FixedState);
+        let sums: HashMap<i32, i32, FixedState> =
+            grouping.fold_with_hasher(FixedState, 0, |accumulator, item| accumulator + item);
+
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&0], 2);
+        assert_eq!(counts[&1], 3);
+        assert_eq!(counts[&2], 2);
+        assert_eq!(sums[&0], 9);
+        assert_eq!(sums[&1], 12);
+        assert_eq!(sums[&2], 7);
+        assert_eq!(
+            counts.into_iter().collect::<HashMap<_, _>>(),
+            grouping.each_count()
+        );
+    }
+
+    #[test]
+    fn test_try_aggregate() {
+        let grouping = vec!["a=1", "b=2", "a=x", "b=3", "a=4"]
+            .grouping_by(|entry| entry.split('=').next().unwrap().to_string());
+        let touched = Cell::new(0);
+        let parse_sum = |_: &String, accumulator: Option<i32>, entry: &&str| {
+            touched.set(touched.get() + 1);
+
+            let value = entry.split('=').nth(1).unwrap().parse::<i32>()?;
+
+            Ok::<_, std::num::ParseIntError>(accumulator.unwrap_or(0) + value)
+        };
+
+        assert!(grouping.try_aggregate(parse_sum).is_err());
+        assert_eq!(touched.get(), 3);
+        assert_eq!(grouping.len(), 5);
+        // "a=x" fails in the middle of group "a"; the grouping keeps every
+        // item, and a retry fails at the same element.
+        assert_eq!(
+            grouping.items_for(&"a".to_string()),
+            vec![&"a=1", &"a=x", &"a=4"]
+        );
+        assert_eq!(
+            grouping.each_count(),
+            HashMap::from([("a".into(), 3), ("b".into(), 2)])
+        );
+
+        touched.set(0);
+
+        assert!(grouping.try_aggregate(parse_sum).is_err());
+        assert_eq!(touched.get(), 3);
+
+        let lenient = grouping
+            .try_fold_with_key(
+                |_, _| 0,
+                |_, accumulator, entry| {
+                    let value = entry.split('=').nth(1).unwrap();
+
+                    Ok::<_, ()>(accumulator + value.parse::<i32>().unwrap_or(0))
+                },
+            )
+            .unwrap();
+
+        assert_eq!(lenient, HashMap::from([("a".into(), 5), ("b".into(), 5)]));
+        assert_eq!(
+            grouping.try_fold_with_key(|_, _| 0, |_, _, _| Err("stop")),
+            Err("stop")
+        );
+    }
+
+    #[test]
+    fn test_filter_and_map_items() {
+        let words = "one two three four five six seven eight nine ten"
+            .split(' ')
+            .collect::<Vec<_>>();
+        let filtered = words
+            .clone()
+            .grouping_by(|s| s.len())
+            .filter(|len, s| *len > 3 || s.starts_with('t'));
+        let prefiltered = words
+            .iter()
+            .copied()
+            .filter(|s| s.len() > 3 || s.starts_with('t'))
+            .collect::<Vec<_>>()
+            .grouping_by(|s| s.len());
+
+        assert_eq!(filtered.each_count(), prefiltered.each_count());
+
+        let initials = filtered.map_items(|s| s.to_uppercase(), |s| s.chars().next().unwrap());
+
+        assert_eq!(
+            initials.each_count(),
+            HashMap::from([('T', 3), ('F', 2), ('S', 1), ('E', 1), ('N', 1)])
+        );
+    }
+
+    #[test]
+    fn test_grouping_by_with() {
+        thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct Order {
+            region: &'static str,
+            total: u32,
+            _lines: Vec<String>,
+        }
+
+        impl Drop for Order {
+            fn drop(&mut self) {
+                DROPS.with(|drops| drops.set(drops.get() + 1));
+            }
+        }
+
+        let orders = || {
+            [("eu", 10), ("na", 5), ("eu", 7), ("ap", 1), ("na", 2)]
+                .into_iter()
+                .map(|(region, total)| Order {
+                    region,
+                    total,
+                    _lines: vec![String::from("line"); 16],
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let totals = orders().grouping_by_with(|order| order.region, |order| order.total);
+
+        assert_eq!(DROPS.with(Cell::get), 5);
+
+        let transformed_later = orders().grouping_by(|order| order.region);
+
+        assert_eq!(totals.each_count(), transformed_later.each_count());
+        assert_eq!(
+            totals.fold(0, |sum, total| sum + total),
+            transformed_later.fold(0, |sum, order| sum + order.total)
+        );
+        assert_eq!(
+            totals.fold(0, |sum, total| sum + total),
+            HashMap::from([("eu", 17), ("na", 7), ("ap", 1)])
+        );
+        assert_eq!(totals.items_for(&"eu"), vec![&10, &7]);
+        assert_eq!(totals.count_of(&"na"), 2);
+        assert_eq!(
+            totals.sum_of(|total| *total),
+            totals.fold(0, |sum, total| sum + total)
+        );
+        assert_eq!(totals.iter().nth(3), Some((&"ap", &1)));
+        assert_eq!(
+            totals.try_aggregate(|_, sum: Option<u32>, total| match sum {
+                Some(_) if *total > 5 => Err(*total),
+                sum => Ok(sum.unwrap_or(0) + total),
+            }),
+            Err(7)
+        );
+    }
+
+    #[test]
+    fn test_then_grouping_by() {
+        let fruits = vec![
+            "apple",
+            "apricot",
+            "banana",
+            "blueberry",
+            "cherry",
+            "citrus",
+            "coconut",
+        ];
+        let first_calls = Cell::new(0);
+        let second_calls = Cell::new(0);
+        let grouping = fruits.grouping_by(|f| {
+            first_calls.set(first_calls.get() + 1);
+            f.chars().next().unwrap()
+        });
+        let nested = grouping.then_grouping_by(|f| {
+            second_calls.set(second_calls.get() + 1);
+            f.len()
+        });
+
+        assert_eq!(
+            nested.to_group_map(),
+            HashMap::from([
+                (
+                    'a',
+                    HashMap::from([(5, vec![&"apple"]), (7, vec![&"apricot"])])
+                ),
+                (
+                    'b',
+                    HashMap::from([(6, vec![&"banana"]), (9, vec![&"blueberry"])])
+                ),
+                (
+                    'c',
+                    HashMap::from([(6, vec![&"cherry", &"citrus"]), (7, vec![&"coconut"])])
+                ),
+            ])
+        );
+        assert_eq!((first_calls.get(), second_calls.get()), (7, 7));
+        assert_eq!(nested.each_count()[&'c'], HashMap::from([(6, 2), (7, 1)]));
+        assert_eq!((first_calls.get(), second_calls.get()), (14, 14));
+        assert_eq!(
+            nested.fold(String::new(), |accumulator, item| accumulator + &item[..1])[&'c'][&6],
+            "cc"
+        );
+    }
+
+    #[test]
+    fn test_single_key_queries() {
+        let orders = vec![(42, "desk"), (7, "lamp"), (42, "chair"), (3, "rug")];
+        let grouping = orders.grouping_by(|(customer, _)| *customer);
+
+        assert_eq!(grouping.items_for(&42), [&(42, "desk"), &(42, "chair")]);
+        assert_eq!(grouping.count_of(&42), 2);
+        assert!(grouping.items_for(&1).is_empty());
+        assert_eq!(grouping.count_of(&1), 0);
+
+        for (customer, count) in grouping.each_count() {
+            assert_eq!(grouping.count_of(&customer), count);
+        }
+    }
+
+    #[test]
+    fn test_borrowed_keys() {
+        struct LogLine {
+            host: String,
+            bytes: usize,
+        }
+
+        let line = |host: &str, bytes| LogLine {
+            host: host.into(),
+            bytes,
+        };
+        let lines = vec![line("a.io", 10), line("b.io", 5), line("a.io", 7)];
+        let by_host = lines.as_slice().grouping_by(|l| l.host.as_str());
+        let counts: HashMap<&str, usize> = by_host.each_count();
+
+        assert_eq!(counts, HashMap::from([("a.io", 2), ("b.io", 1)]));
+        assert_eq!(
+            by_host.fold(0, |total, l| total + l.bytes),
+            HashMap::from([("a.io", 17), ("b.io", 5)])
+        );
+        assert!(std::ptr::eq(*by_host.items_for(&"b.io")[0], &lines[1]));
+    }
+}
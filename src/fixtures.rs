@@ -0,0 +1,16 @@
+//! Shared test data.
+
+/// `len` pseudo-random numbers from a xorshift generator, so tests comparing
+/// parallel and sequential results see the same input for the same `seed`.
+pub(crate) fn random_data(seed: u64, len: usize) -> Vec<u64> {
+    let mut state = seed;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::{hash_map, HashMap, HashSet},
+    fmt::{self, Display},
+    hash::Hash,
+    iter::Sum,
+    ops::Deref,
+};
+
+use crate::maps::merge_maps;
+
+/// A grouping result with the follow-up helpers most callers end up writing
+/// themselves. It derefs to the wrapped `HashMap` for everything else.
+#[derive(Clone, Debug)]
+pub struct GroupedMap<K, V>(HashMap<K, V>);
+
+impl<K, V> GroupedMap<K, V> {
+    pub fn into_inner(self) -> HashMap<K, V> {
+        self.0
+    }
+
+    pub fn len_groups(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<K, V> GroupedMap<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn map_values<W>(self, mut f: impl FnMut(V) -> W) -> GroupedMap<K, W> {
+        GroupedMap(
+            self.0
+                .into_iter()
+                .map(|(key, value)| (key, f(value)))
+                .collect(),
+        )
+    }
+
+    /// Keeps the groups `predicate` accepts, e.g. to drop small ones.
+    pub fn filter_groups(mut self, predicate: impl FnMut(&K, &mut V) -> bool) -> Self {
+        self.retain(predicate);
+        self
+    }
+
+    /// In-place [`filter_groups`](Self::filter_groups).
+    pub fn retain(&mut self, predicate: impl FnMut(&K, &mut V) -> bool) {
+        self.0.retain(predicate);
+    }
+
+    /// The value of `key`, or `V::default()` (zero for counts) if no item
+    /// had that key.
+    pub fn copied_or_zero(&self, key: &K) -> V
+    where
+        V: Copy + Default,
+    {
+        self.0.get(key).copied().unwrap_or_default()
+    }
+
+    /// Merges `other` into `self`: keys on one side only pass through and
+    /// `combine` is called exactly once per shared key, with `self`'s value
+    /// first.
+    pub fn merge_with(mut self, other: Self, combine: impl FnMut(&K, V, V) -> V) -> Self {
+        merge_maps(&mut self.0, other.0, combine);
+        self
+    }
+
+    /// Sum of all values, e.g. the number of items behind an `each_count`.
+    pub fn total<'a>(&'a self) -> V
+    where
+        V: Sum<&'a V>,
+    {
+        self.0.values().sum()
+    }
+}
+
+impl<K, V> GroupedMap<K, V>
+where
+    K: Ord,
+{
+    /// Groups in ascending key order.
+    pub fn sorted_iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        entries.into_iter()
+    }
+
+    /// Groups from largest to smallest, equal sizes in ascending key order.
+    pub fn iter_by_size_desc(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        V: HasLen,
+    {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+
+        entries.sort_unstable_by(|(a_key, a), (b_key, b)| {
+            b.len().cmp(&a.len()).then_with(|| a_key.cmp(b_key))
+        });
+        entries.into_iter()
+    }
+}
+
+impl<K, T> GroupedMap<K, Vec<T>>
+where
+    K: Ord,
+{
+    /// All items, group after group in key order, each group in stored order.
+    pub fn flatten(self) -> Vec<T> {
+        self.into_sorted_groups().into_iter().flatten().collect()
+    }
+
+    /// Interleaves the groups in key order, one item of each per round, until
+    /// all are exhausted: no group gives its `i + 1`-th item before every
+    /// group that has one gave its `i`-th. Useful for fair scheduling.
+    pub fn round_robin(self) -> Vec<T> {
+        let mut groups = self
+            .into_sorted_groups()
+            .into_iter()
+            .map(Vec::into_iter)
+            .collect::<Vec<_>>();
+        let mut items = Vec::with_capacity(groups.iter().map(|group| group.len()).sum());
+
+        while !groups.is_empty() {
+            groups.retain_mut(|group| match group.next() {
+                Some(item) => {
+                    items.push(item);
+                    true
+                }
+                None => false,
+            });
+        }
+
+        items
+    }
+
+    fn into_sorted_groups(self) -> Vec<Vec<T>> {
+        let mut groups = self.0.into_iter().collect::<Vec<_>>();
+
+        groups.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        groups.into_iter().map(|(_, items)| items).collect()
+    }
+}
+
+impl<K> GroupedMap<K, usize>
+where
+    K: Eq + Hash,
+{
+    /// [`merge_with`](Self::merge_with) adding the counts of shared keys.
+    pub fn merge_counts(self, other: Self) -> Self {
+        self.merge_with(other, |_, a, b| a + b)
+    }
+}
+
+impl<K, V> PartialEq for GroupedMap<K, V>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K, V> Eq for GroupedMap<K, V>
+where
+    K: Eq + Hash,
+    V: Eq,
+{
+}
+
+impl<K, V> Default for GroupedMap<K, V> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<K, V> Deref for GroupedMap<K, V> {
+    type Target = HashMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for GroupedMap<K, V> {
+    fn from(map: HashMap<K, V>) -> Self {
+        Self(map)
+    }
+}
+
+impl<K, V> From<GroupedMap<K, V>> for HashMap<K, V> {
+    fn from(map: GroupedMap<K, V>) -> Self {
+        map.0
+    }
+}
+
+impl<K, V> IntoIterator for GroupedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = hash_map::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a GroupedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = hash_map::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Writes `{key=value, ...}` with keys in ascending order, so the output is
+/// the same on every run.
+impl<K, V> Display for GroupedMap<K, V>
+where
+    K: Ord + Display,
+    V: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+
+        for (position, (key, value)) in self.sorted_iter().enumerate() {
+            if position > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{}={}", key, value)?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+/// Size of a group value, as used by [`GroupedMap::iter_by_size_desc`]. For
+/// counts, the size is the count itself.
+pub trait HasLen {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> HasLen for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+impl<T, S> HasLen for HashSet<T, S> {
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+}
+
+impl HasLen for usize {
+    fn len(&self) -> usize {
+        *self
+    }
+}
+
+/// Wraps a terminal's result, e.g. `grouping.each_count().into_grouped()`.
+pub trait IntoGroupedMap<K, V> {
+    fn into_grouped(self) -> GroupedMap<K, V>;
+}
+
+impl<K, V> IntoGroupedMap<K, V> for HashMap<K, V> {
+    fn into_grouped(self) -> GroupedMap<K, V> {
+        GroupedMap(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use super::{GroupedMap, IntoGroupedMap};
+    use crate::lazy::IntoGrouping;
+
+    #[test]
+    fn test_grouped_map_helpers() {
+        let counts = ["b", "a", "c", "a", "b", "a"]
+            .into_iter()
+            .grouping_by(|s| *s)
+            .each_count()
+            .into_grouped();
+
+        assert_eq!(counts.len_groups(), 3);
+        assert_eq!(counts.copied_or_zero(&"a"), 3);
+        assert_eq!(counts.copied_or_zero(&"z"), 0);
+        assert_eq!(counts.get("b"), Some(&2));
+        assert_eq!(counts.total(), 6);
+        assert_eq!(counts.to_string(), "{a=3, b=2, c=1}");
+        assert_eq!(GroupedMap::<u8, usize>::default().to_string(), "{}");
+
+        let plain: HashMap<_, _> = counts.clone().into();
+
+        assert_eq!(GroupedMap::from(plain.clone()), counts);
+        assert_eq!(counts.into_inner(), plain);
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let a = HashMap::from([("x", 1), ("y", 2)]).into_grouped();
+        let b = HashMap::from([("z", 3)]).into_grouped();
+        let c = HashMap::from([("x", 10), ("z", 30)]).into_grouped();
+
+        assert_eq!(
+            a.clone().merge_counts(b.clone()),
+            HashMap::from([("x", 1), ("y", 2), ("z", 3)]).into_grouped()
+        );
+        assert_eq!(
+            a.clone()
+                .merge_with(c.clone(), |_, mine, theirs| mine.max(theirs)),
+            HashMap::from([("x", 10), ("y", 2), ("z", 30)]).into_grouped()
+        );
+
+        let left = a.clone().merge_counts(b.clone()).merge_counts(c.clone());
+        let right = a.merge_counts(b.merge_counts(c));
+
+        assert_eq!(left, right);
+        assert_eq!(left.total(), 46);
+    }
+
+    #[test]
+    fn test_map_values_and_filter_groups() {
+        let mut groups = (1..=10)
+            .grouping_by(|n| n % 3)
+            .into_group_map()
+            .into_grouped();
+
+        assert_eq!(
+            groups.clone().map_values(|items| items.len()),
+            HashMap::from([(0, 3), (1, 4), (2, 3)]).into_grouped()
+        );
+        assert_eq!(
+            groups.clone().filter_groups(|_, items| items.len() > 3),
+            HashMap::from([(1, vec![1, 4, 7, 10])]).into_grouped()
+        );
+
+        groups.retain(|key, items| {
+            items.retain(|n| n % 2 == 0);
+            *key != 0
+        });
+        assert_eq!(
+            groups,
+            HashMap::from([(1, vec![4, 10]), (2, vec![2, 8])]).into_grouped()
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let groups = HashMap::from([("b", vec![3, 4]), ("a", vec![1, 2]), ("c", vec![])]);
+
+        assert_eq!(groups.into_grouped().flatten(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let groups = HashMap::from([
+            ("a", vec!["a1", "a2", "a3"]),
+            ("b", vec!["b1"]),
+            ("c", vec!["c1", "c2"]),
+            ("d", vec![]),
+        ]);
+        let order = groups.clone().into_grouped().round_robin();
+
+        assert_eq!(order, ["a1", "b1", "c1", "a2", "c2", "a3"]);
+
+        // Fairness: an item's round is its index within its group, and rounds
+        // never go backwards.
+        let rounds = order
+            .iter()
+            .map(|item| {
+                groups
+                    .values()
+                    .find_map(|items| items.iter().position(|i| i == item))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(rounds.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_sorted_iter() {
+        let counts = HashMap::from([("pear", 2), ("apple", 5), ("fig", 2)]).into_grouped();
+
+        assert_eq!(
+            counts.sorted_iter().collect::<Vec<_>>(),
+            [(&"apple", &5), (&"fig", &2), (&"pear", &2)]
+        );
+    }
+
+    #[test]
+    fn test_iter_by_size_desc() {
+        let counts =
+            HashMap::from([("pear", 2), ("apple", 5), ("fig", 2), ("kiwi", 7)]).into_grouped();
+
+        assert_eq!(
+            counts
+                .iter_by_size_desc()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            ["kiwi", "apple", "fig", "pear"]
+        );
+
+        let groups =
+            HashMap::from([(3, vec!['a']), (1, vec!['b', 'c']), (2, vec!['d'])]).into_grouped();
+
+        assert_eq!(
+            groups
+                .iter_by_size_desc()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+
+        let sets =
+            HashMap::from([("x", HashSet::from([1])), ("y", HashSet::from([1, 2]))]).into_grouped();
+
+        assert_eq!(
+            sets.iter_by_size_desc().next().map(|(key, _)| *key),
+            Some("y")
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hash, Hasher},
+};
+
+/// Compact stand-in for an interned key, only meaningful together with the
+/// [`KeyInterner`] that issued it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Maps keys to dense [`Symbol`]s, allocating each distinct key once no
+/// matter how many groupings or merges it takes part in.
+#[derive(Clone, Debug)]
+pub struct KeyInterner<K = String> {
+    // Symbols are found by key hash, chaining colliding ones through `next`,
+    // so the keys themselves are only stored in `keys`.
+    heads: HashMap<u64, Symbol>,
+    next: Vec<Option<Symbol>>,
+    keys: Vec<K>,
+    hasher: RandomState,
+}
+
+impl<K> KeyInterner<K>
+where
+    K: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            heads: HashMap::new(),
+            next: Vec::new(),
+            keys: Vec::new(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns the symbol of `key`, interning an owned copy on first sight.
+    ///
+    /// # Panics
+    ///
+    /// Panics when more than `u32::MAX` distinct keys are interned.
+    pub fn intern<Q>(&mut self, key: &Q) -> Symbol
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+    {
+        let hash = self.hash(key);
+
+        if let Some(symbol) = self.find(hash, key) {
+            return symbol;
+        }
+
+        let symbol = Symbol(u32::try_from(self.keys.len()).expect("too many interned keys"));
+
+        self.next.push(self.heads.insert(hash, symbol));
+        self.keys.push(key.to_owned());
+        symbol
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<Symbol>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(self.hash(key), key)
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> Option<&K> {
+        self.keys.get(symbol.index())
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<K> KeyInterner<K> {
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        let mut hasher = self.hasher.build_hasher();
+
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<Symbol>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mut candidate = self.heads.get(&hash).copied();
+
+        while let Some(symbol) = candidate {
+            if self.keys[symbol.index()].borrow() == key {
+                return Some(symbol);
+            }
+
+            candidate = self.next[symbol.index()];
+        }
+
+        None
+    }
+}
+
+impl<K> Default for KeyInterner<K>
+where
+    K: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::{Hash, Hasher};
+
+    use super::KeyInterner;
+    use crate::lazy::IntoGrouping;
+
+    #[test]
+    fn test_grouping_by_interned() {
+        let monday = ["GET /", "POST /login", "GET /about", "DELETE /x"];
+        let tuesday = ["PUT /y", "GET /", "GET /"];
+        let mut interner = KeyInterner::new();
+
+        let first = monday
+            .iter()
+            .grouping_by_interned(|line| line.split(' ').next().unwrap(), &mut interner)
+            .each_count();
+        let second = tuesday
+            .iter()
+            .grouping_by_interned(|line| line.split(' ').next().unwrap(), &mut interner)
+            .each_count();
+
+        assert_eq!(interner.len(), 4);
+
+        let get = interner.get("GET").unwrap();
+
+        assert_eq!(first[&get], 2);
+        assert_eq!(second[&get], 2);
+        assert_eq!(interner.intern("GET"), get);
+        assert_eq!(interner.resolve(get).map(String::as_str), Some("GET"));
+
+        let resolved = first
+            .iter()
+            .map(|(symbol, count)| (interner.resolve(*symbol).unwrap().as_str(), *count))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        assert_eq!(
+            resolved,
+            monday
+                .iter()
+                .grouping_by(|line| line.split(' ').next().unwrap())
+                .each_count()
+        );
+    }
+
+    #[test]
+    fn test_intern_colliding_hashes() {
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        struct Colliding(&'static str);
+
+        impl Hash for Colliding {
+            fn hash<H: Hasher>(&self, _: &mut H) {}
+        }
+
+        let mut interner = KeyInterner::new();
+        let symbols = ["a", "b", "c", "b", "a"].map(|key| interner.intern(&Colliding(key)));
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!(symbols[0], symbols[4]);
+        assert_eq!(symbols[1], symbols[3]);
+        assert_ne!(symbols[0], symbols[2]);
+        assert_eq!(interner.get(&Colliding("c")), Some(symbols[2]));
+        assert_eq!(interner.get(&Colliding("d")), None);
+        assert_eq!(interner.resolve(symbols[1]), Some(&Colliding("b")));
+    }
+}
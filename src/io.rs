@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::{self, BufRead},
+};
+
+use crate::lazy::IntoGrouping;
+
+/// Groups the lines of `reader` without collecting them first. Line endings
+/// are stripped as in [`BufRead::lines`], and the first IO error stops the
+/// aggregation and is returned.
+pub fn group_lines<R, K, Ks, O>(
+    reader: impl BufRead,
+    mut key_selector: Ks,
+    operation: O,
+) -> io::Result<HashMap<K, R>>
+where
+    K: Eq + Hash,
+    Ks: FnMut(&str) -> K,
+    O: FnMut(&K, Option<R>, String) -> R,
+{
+    let mut lines = reader.lines();
+    let mut error = None;
+    let groups = std::iter::from_fn(|| match lines.next()? {
+        Ok(line) => Some(line),
+        Err(err) => {
+            error = Some(err);
+            None
+        }
+    })
+    .grouping_by(|line| key_selector(line))
+    .aggregate(operation);
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(groups),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, BufReader, Read};
+
+    use super::group_lines;
+
+    #[test]
+    fn test_group_lines() {
+        let log = "\
+127.0.0.1 GET /index.html 200
+127.0.0.1 GET /missing 404
+10.0.0.2 POST /login 200
+10.0.0.3 GET /admin 403
+10.0.0.2 GET /index.html 200";
+        let by_status = group_lines(
+            log.as_bytes(),
+            |line| line.rsplit(' ').next().unwrap().to_string(),
+            |_, accumulator: Option<Vec<String>>, line| {
+                let mut accumulator = accumulator.unwrap_or_default();
+                accumulator.push(line);
+                accumulator
+            },
+        )
+        .unwrap();
+
+        assert_eq!(by_status.len(), 3);
+        assert_eq!(by_status["200"].len(), 3);
+        assert_eq!(by_status["404"], vec!["127.0.0.1 GET /missing 404"]);
+        assert_eq!(by_status["403"], vec!["10.0.0.3 GET /admin 403"]);
+        assert_eq!(by_status["200"][2], "10.0.0.2 GET /index.html 200");
+    }
+
+    #[test]
+    fn test_group_lines_propagates_errors() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "disk on fire"))
+            }
+        }
+
+        let reader = "a\nb\n".as_bytes().chain(BufReader::new(FailingReader));
+        let result = group_lines(
+            BufReader::new(reader),
+            |line| line.len(),
+            |_, accumulator: Option<usize>, _| accumulator.unwrap_or(0) + 1,
+        );
+
+        assert_eq!(result.unwrap_err().to_string(), "disk on fire");
+    }
+}
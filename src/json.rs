@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    hash::Hash,
+    io::Read,
+    marker::PhantomData,
+};
+
+use serde::{
+    de::{DeserializeOwned, SeqAccess, Visitor},
+    Deserializer, Serialize,
+};
+use serde_json::{ser::PrettyFormatter, Number, Value};
+
+use crate::lazy::IntoGrouping;
+
+/// Groups the elements of a top-level JSON array as they are parsed, so the
+/// array itself is never held in memory.
+///
+/// `serde_json::StreamDeserializer` only splits whitespace-separated values,
+/// so the array is walked through `SeqAccess` instead, feeding each element to
+/// [`Grouping::aggregate`](crate::lazy::Grouping::aggregate). Wrap unbuffered
+/// readers in a `BufReader`.
+pub fn group_from_json_array<T, R, K, Rd, Ks, O>(
+    reader: Rd,
+    key_selector: Ks,
+    operation: O,
+) -> serde_json::Result<HashMap<K, R>>
+where
+    T: DeserializeOwned,
+    K: Eq + Hash,
+    Rd: Read,
+    Ks: FnMut(&T) -> K,
+    O: FnMut(&K, Option<R>, T) -> R,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let groups = deserializer.deserialize_seq(GroupingVisitor {
+        key_selector,
+        operation,
+        marker: PhantomData,
+    })?;
+
+    deserializer.end()?;
+
+    Ok(groups)
+}
+
+struct GroupingVisitor<T, R, K, Ks, O> {
+    key_selector: Ks,
+    operation: O,
+    marker: PhantomData<fn(T) -> (K, R)>,
+}
+
+impl<'de, T, R, K, Ks, O> Visitor<'de> for GroupingVisitor<T, R, K, Ks, O>
+where
+    T: DeserializeOwned,
+    K: Eq + Hash,
+    Ks: FnMut(&T) -> K,
+    O: FnMut(&K, Option<R>, T) -> R,
+{
+    type Value = HashMap<K, R>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a JSON array")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut error = None;
+        let elements = std::iter::from_fn(|| match seq.next_element::<T>() {
+            Ok(element) => element,
+            Err(err) => {
+                error = Some(err);
+                None
+            }
+        });
+        let groups = elements
+            .grouping_by(self.key_selector)
+            .aggregate(self.operation);
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(groups),
+        }
+    }
+}
+
+pub trait IntoPrettyJson {
+    /// Renders a grouping result as a JSON object indented by `indent` spaces,
+    /// with keys stringified through `Display` and sorted.
+    ///
+    /// Groups whose keys display the same are merged into one entry: numbers
+    /// are summed, arrays concatenated and objects merged key by key. Colliding
+    /// values are merged in the order of their serialized JSON, so the output
+    /// stays deterministic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value fails to serialize, e.g. a nested map with non-string
+    /// keys, or if colliding values cannot be merged, e.g. two strings or a
+    /// sum overflowing.
+    fn into_pretty_json(self, indent: usize) -> String;
+}
+
+impl<K, V, S> IntoPrettyJson for HashMap<K, V, S>
+where
+    K: Display,
+    V: Serialize,
+{
+    fn into_pretty_json(self, indent: usize) -> String {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+
+        for (key, value) in self {
+            let value = serde_json::to_value(value)
+                .expect("grouping result values should serialize to JSON");
+
+            groups.entry(key.to_string()).or_default().push(value);
+        }
+
+        let sorted = groups
+            .into_iter()
+            .map(|(key, mut values)| {
+                if values.len() > 1 {
+                    values.sort_by_cached_key(Value::to_string);
+                }
+
+                let value = values
+                    .into_iter()
+                    .reduce(|left, right| merge_values(&key, left, right))
+                    .expect("every key holds at least one value");
+
+                (key, value)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let indent = vec![b' '; indent];
+        let mut json = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(
+            &mut json,
+            PrettyFormatter::with_indent(&indent),
+        );
+
+        sorted
+            .serialize(&mut serializer)
+            .expect("JSON values always serialize");
+
+        String::from_utf8(json).expect("serde_json emits UTF-8")
+    }
+}
+
+fn merge_values(key: &str, left: Value, right: Value) -> Value {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => match add_numbers(&left, &right) {
+            Some(sum) => Value::Number(sum),
+            None => panic!("sum of the groups displayed as {key:?} overflows: {left} + {right}"),
+        },
+        (Value::Array(mut left), Value::Array(right)) => {
+            left.extend(right);
+            Value::Array(left)
+        }
+        (Value::Object(mut left), Value::Object(right)) => {
+            for (field, right) in right {
+                let merged = match left.remove(&field) {
+                    Some(left) => merge_values(key, left, right),
+                    None => right,
+                };
+
+                left.insert(field, merged);
+            }
+
+            Value::Object(left)
+        }
+        (left, right) => {
+            panic!(
+                "groups displayed as {key:?} hold values that cannot be merged: {left} and {right}"
+            )
+        }
+    }
+}
+
+fn add_numbers(left: &Number, right: &Number) -> Option<Number> {
+    if let (Some(left), Some(right)) = (left.as_u64(), right.as_u64()) {
+        return left.checked_add(right).map(Number::from);
+    }
+    if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+        return left.checked_add(right).map(Number::from);
+    }
+
+    Number::from_f64(left.as_f64()? + right.as_f64()?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, fmt};
+
+    use serde_json::Value;
+
+    use super::{group_from_json_array, IntoPrettyJson};
+    use crate::lazy::IntoGrouping;
+
+    #[test]
+    fn test_group_from_json_array() {
+        let events = r#"[
+            {"type": "click", "target": "a"},
+            {"type": "view", "target": "b"},
+            {"type": "click", "target": "c"},
+            {"type": "scroll", "target": "a"},
+            {"type": "click", "target": "b"}
+        ]"#;
+        let counts = group_from_json_array(
+            events.as_bytes(),
+            |event: &Value| event["type"].as_str().unwrap().to_string(),
+            |_, accumulator: Option<usize>, _| accumulator.unwrap_or(0) + 1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            counts,
+            HashMap::from([
+                ("click".to_string(), 3),
+                ("view".to_string(), 1),
+                ("scroll".to_string(), 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_group_from_json_array_rejects_malformed_input() {
+        let result = group_from_json_array(
+            r#"[{"type": "click"}, {"type": "#.as_bytes(),
+            |event: &Value| event["type"].as_str().map(str::to_string),
+            |_, accumulator: Option<usize>, _| accumulator.unwrap_or(0) + 1,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_into_pretty_json() {
+        let counts = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5]
+            .into_iter()
+            .grouping_by(|i| i % 3)
+            .each_count();
+
+        assert_eq!(
+            counts.into_pretty_json(2),
+            "{\n  \"0\": 4,\n  \"1\": 3,\n  \"2\": 4\n}"
+        );
+
+        let groups = HashMap::from([('b', vec![1, 2]), ('a', vec![])]);
+
+        assert_eq!(
+            groups.into_pretty_json(4),
+            "{\n    \"a\": [],\n    \"b\": [\n        1,\n        2\n    ]\n}"
+        );
+    }
+
+    #[derive(PartialEq, Eq, Hash)]
+    struct Shard(u32);
+
+    impl fmt::Display for Shard {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "shard-{}", self.0 % 2)
+        }
+    }
+
+    #[test]
+    fn test_into_pretty_json_merges_colliding_keys() {
+        let counts = (0..10u32).grouping_by(|n| Shard(n % 4)).each_count();
+
+        assert_eq!(
+            counts.into_pretty_json(2),
+            "{\n  \"shard-0\": 5,\n  \"shard-1\": 5\n}"
+        );
+
+        let groups = (0..6u32).grouping_by(|n| Shard(n % 4)).into_group_map();
+
+        assert_eq!(
+            groups.into_pretty_json(0),
+            "{\n\"shard-0\": [\n0,\n4,\n2\n],\n\"shard-1\": [\n1,\n5,\n3\n]\n}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be merged")]
+    fn test_into_pretty_json_rejects_unmergeable_collisions() {
+        HashMap::from([(Shard(0), "zero"), (Shard(2), "two")]).into_pretty_json(2);
+    }
+}
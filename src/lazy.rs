@@ -0,0 +1,3176 @@
+use std::{
+    borrow::Borrow,
+    cmp::{Ordering, Reverse},
+    collections::{
+        hash_map::{Entry, RandomState},
+        HashMap, HashSet,
+    },
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter,
+    ops::{Add, Range},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    intern::{KeyInterner, Symbol},
+    maps,
+    registry::{KeySelectorRegistry, RegisteredKeySelector, UnknownKeySelector},
+};
+
+#[derive(Clone)]
+pub struct Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    pub(crate) iter: I,
+    key_selector: Ks,
+    expected_groups: Option<usize>,
+}
+
+/// Upper bound on the number of groups guessed from a size hint alone.
+const MAX_GUESSED_GROUPS: usize = 1 << 10;
+
+impl<I, Ks, K> Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    pub(crate) fn new(iter: I, key_selector: Ks) -> Self {
+        Self {
+            iter,
+            key_selector,
+            expected_groups: None,
+        }
+    }
+
+    /// Sizes the result maps of the terminals for `groups` distinct keys up
+    /// front. Without a hint the terminals guess from the source's size hint,
+    /// which only helps for sources that report one (slices, ranges, `Vec`s);
+    /// an explicit hint pays off when the number of groups is known and large.
+    /// Results are the same either way.
+    pub fn with_expected_groups(mut self, groups: usize) -> Self {
+        self.expected_groups = Some(groups);
+        self
+    }
+
+    fn initial_capacity(&self) -> usize {
+        self.expected_groups.unwrap_or_else(|| {
+            let (lower, _) = self.iter.size_hint();
+
+            ((lower as f64).sqrt() as usize).min(MAX_GUESSED_GROUPS)
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn then_by<K2, Ks2>(
+        self,
+        mut secondary_selector: Ks2,
+    ) -> Grouping<I, impl FnMut(&I::Item) -> (K, K2), (K, K2)>
+    where
+        Ks2: FnMut(&I::Item) -> K2,
+    {
+        let mut key_selector = self.key_selector;
+
+        Grouping {
+            iter: self.iter,
+            key_selector: move |item: &I::Item| (key_selector(item), secondary_selector(item)),
+            expected_groups: self.expected_groups,
+        }
+    }
+}
+
+impl<I, Ks, K> Iterator for Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    type Item = (K, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        let key = (self.key_selector)(&item);
+
+        Some((key, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Marks the first item of every run of equal consecutive keys, see
+/// [`IntoGrouping::with_key_boundaries`].
+#[derive(Clone)]
+pub struct KeyBoundaries<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    grouping: Grouping<I, Ks, K>,
+    previous: Option<K>,
+}
+
+impl<I, Ks, K> Iterator for KeyBoundaries<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: PartialEq + Clone,
+{
+    type Item = (bool, K, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, item) = self.grouping.next()?;
+        let boundary = self.previous.as_ref() != Some(&key);
+
+        if boundary {
+            self.previous = Some(key.clone());
+        }
+
+        Some((boundary, key, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.grouping.size_hint()
+    }
+}
+
+/// Groups of consecutive items with equal keys, see
+/// [`IntoGrouping::streaming_groups_sorted`].
+pub struct SortedGroups<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    grouping: Grouping<I, Ks, K>,
+    pending: Option<(K, I::Item)>,
+}
+
+impl<I, Ks, K> Iterator for SortedGroups<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: PartialEq,
+{
+    type Item = (K, Vec<I::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, first) = self.pending.take().or_else(|| self.grouping.next())?;
+        let mut group = vec![first];
+
+        for (next_key, item) in self.grouping.by_ref() {
+            if next_key != key {
+                self.pending = Some((next_key, item));
+                break;
+            }
+
+            group.push(item);
+        }
+
+        Some((key, group))
+    }
+}
+
+/// Group boundaries of a slice sorted (or at least clustered) by key, for
+/// slicing the original data instead of copying items into groups.
+pub trait GroupRanges<T> {
+    /// Each run of equal consecutive keys with its `start..end` index range.
+    /// Together the ranges partition the slice in order; as with
+    /// [`IntoGrouping::streaming_groups_sorted`], a key that reappears after a
+    /// different one gets a new range.
+    fn group_ranges_sorted<K, Ks>(&self, key_selector: Ks) -> Vec<(K, Range<usize>)>
+    where
+        Ks: FnMut(&T) -> K,
+        K: PartialEq;
+}
+
+impl<T> GroupRanges<T> for [T] {
+    fn group_ranges_sorted<K, Ks>(&self, mut key_selector: Ks) -> Vec<(K, Range<usize>)>
+    where
+        Ks: FnMut(&T) -> K,
+        K: PartialEq,
+    {
+        let mut ranges: Vec<(K, Range<usize>)> = Vec::new();
+
+        for (index, item) in self.iter().enumerate() {
+            let key = key_selector(item);
+
+            match ranges.last_mut() {
+                Some((last, range)) if *last == key => range.end = index + 1,
+                _ => ranges.push((key, index..index + 1)),
+            }
+        }
+
+        ranges
+    }
+}
+
+/// Grouping on a normalized key that remembers the first raw key of every
+/// group, see [`IntoGrouping::grouping_by_normalized`].
+pub struct NormalizedGrouping<I, Ks, K, N>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+{
+    grouping: Grouping<I, Ks, K>,
+    normalize: N,
+}
+
+impl<I, Ks, K, N> NormalizedGrouping<I, Ks, K, N>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: Clone,
+{
+    /// Aggregates every normalized group and pairs the result with the raw
+    /// key of the group's first item.
+    pub fn aggregate<K2, R, O>(mut self, mut operation: O) -> HashMap<K2, (K, R)>
+    where
+        N: FnMut(K) -> K2,
+        K2: Eq + Hash,
+        O: FnMut(&K2, Option<R>, I::Item) -> R,
+    {
+        let mut m: HashMap<K2, (K, R)> = HashMap::new();
+
+        for (raw, item) in self.grouping.by_ref() {
+            match m.entry((self.normalize)(raw.clone())) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, (raw, accumulator)| {
+                        (raw, operation(key, Some(accumulator), item))
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, item);
+
+                    entry.insert((raw, value));
+                }
+            }
+        }
+
+        m
+    }
+
+    pub fn each_count<K2>(self) -> HashMap<K2, (K, usize)>
+    where
+        N: FnMut(K) -> K2,
+        K2: Eq + Hash,
+    {
+        self.aggregate(|_, accumulator, _| accumulator.unwrap_or(0) + 1)
+    }
+
+    pub fn into_group_map<K2>(self) -> HashMap<K2, (K, Vec<I::Item>)>
+    where
+        N: FnMut(K) -> K2,
+        K2: Eq + Hash,
+    {
+        self.aggregate(|_, accumulator: Option<Vec<I::Item>>, item| {
+            let mut accumulator = accumulator.unwrap_or_default();
+            accumulator.push(item);
+            accumulator
+        })
+    }
+}
+
+/// What [`Grouping::aggregate_bounded_with_policy`] does with an item whose
+/// key would exceed the distinct-key cap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy<K> {
+    /// Stop and return [`KeyLimitExceeded`].
+    Error,
+    /// Drop items with new keys, keep aggregating known ones.
+    DropNew,
+    /// Route items with new keys into this bucket, which does not count
+    /// towards the cap.
+    Other(K),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyLimitExceeded<K> {
+    pub max_keys: usize,
+    pub key: K,
+}
+
+impl<K> fmt::Display for KeyLimitExceeded<K>
+where
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {:?} exceeds the limit of {} distinct keys",
+            self.key, self.max_keys
+        )
+    }
+}
+
+impl<K> Error for KeyLimitExceeded<K> where K: fmt::Debug {}
+
+#[allow(dead_code)]
+impl<I, Ks, K> Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: Eq + Hash,
+{
+    pub fn aggregate<R, O>(self, operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+    {
+        self.aggregate_with_hasher(RandomState::new(), operation)
+    }
+
+    pub fn aggregate_with_hasher<R, O, S>(mut self, hasher: S, mut operation: O) -> HashMap<K, R, S>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+        S: BuildHasher + Clone,
+    {
+        let mut m: HashMap<K, R, S> =
+            HashMap::with_capacity_and_hasher(self.initial_capacity(), hasher);
+
+        for (key, value) in self.by_ref() {
+            match m.entry(key) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), value)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, value);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        m
+    }
+
+    /// Aggregates every `batch_size` items into a fresh map, e.g. to flush
+    /// bounded partial results to a sink. The last batch may be smaller; an
+    /// empty source yields no maps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn aggregate_batched<R, O>(
+        mut self,
+        batch_size: usize,
+        mut operation: O,
+    ) -> impl Iterator<Item = HashMap<K, R>>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+    {
+        assert!(batch_size > 0, "batch size must be positive");
+
+        iter::from_fn(move || {
+            let mut m = HashMap::with_capacity(self.initial_capacity().min(batch_size));
+            let mut received = false;
+
+            for (key, value) in self.by_ref().take(batch_size) {
+                received = true;
+
+                match m.entry(key) {
+                    Entry::Occupied(entry) => {
+                        maps::update_entry(entry, |key, accumulator| {
+                            operation(key, Some(accumulator), value)
+                        });
+                    }
+                    Entry::Vacant(entry) => {
+                        let value = operation(entry.key(), None, value);
+
+                        entry.insert(value);
+                    }
+                }
+            }
+
+            received.then_some(m)
+        })
+    }
+
+    /// Aggregates into an existing map, e.g. one kept across batches. Keys
+    /// already in `destination` hand their value to `operation` as
+    /// `Some(accumulator)`.
+    pub fn aggregate_into<R, O>(
+        self,
+        destination: &mut HashMap<K, R>,
+        mut operation: O,
+    ) -> &mut HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+    {
+        for (key, value) in self {
+            match destination.entry(key) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), value)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, value);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        destination
+    }
+
+    /// Like [`aggregate`](Self::aggregate) with a fallible `operation`, where
+    /// a failure only spoils its own group. A group that fails once yields
+    /// `Err` with every error it produced and no partial value: later items
+    /// of that group are still handed to `operation`, with `None` as the
+    /// accumulator, so their errors are recorded too, while their successful
+    /// results are dropped.
+    pub fn aggregate_collecting_errors<R, E, O>(
+        self,
+        mut operation: O,
+    ) -> HashMap<K, Result<R, Vec<E>>>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> Result<R, E>,
+    {
+        self.aggregate(
+            |key, accumulator: Option<Result<R, Vec<E>>>, item| match accumulator {
+                Some(Err(mut errors)) => {
+                    if let Err(error) = operation(key, None, item) {
+                        errors.push(error);
+                    }
+
+                    Err(errors)
+                }
+                accumulator => {
+                    operation(key, accumulator.and_then(Result::ok), item).map_err(|e| vec![e])
+                }
+            },
+        )
+    }
+
+    pub fn fold_with_key<R, Ivs, O>(
+        self,
+        mut initial_value_selector: Ivs,
+        mut operation: O,
+    ) -> HashMap<K, R>
+    where
+        Ivs: FnMut(&K, &I::Item) -> R,
+        O: FnMut(&K, R, I::Item) -> R,
+    {
+        self.aggregate(|key, accumulator, item| {
+            operation(
+                key,
+                accumulator.unwrap_or(initial_value_selector(key, &item)),
+                item,
+            )
+        })
+    }
+
+    pub fn fold_with<R, Ivg, O>(
+        self,
+        mut initial_value_provider: Ivg,
+        mut operation: O,
+    ) -> HashMap<K, R>
+    where
+        Ivg: FnMut() -> R,
+        O: FnMut(&K, R, I::Item) -> R,
+    {
+        self.aggregate(|key, accumulator, item| {
+            operation(key, accumulator.unwrap_or(initial_value_provider()), item)
+        })
+    }
+
+    pub fn fold<R, O>(self, initial_value: R, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(R, I::Item) -> R,
+        R: Clone,
+    {
+        self.aggregate(|_, accumulator, item| {
+            operation(accumulator.unwrap_or(initial_value.clone()), item)
+        })
+    }
+
+    pub fn reduce_with_key<R, O>(self, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, R, I::Item) -> R,
+        I::Item: Into<R>,
+    {
+        self.aggregate(|key, accumulator, item| {
+            if let Some(accumulator) = accumulator {
+                operation(key, accumulator, item)
+            } else {
+                item.into()
+            }
+        })
+    }
+
+    pub fn reduce<R, O>(self, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(R, I::Item) -> R,
+        I::Item: Into<R>,
+    {
+        self.reduce_with_key(|_, accumulator, item| operation(accumulator, item))
+    }
+
+    /// Maps every distinct key to `value(key, first_item)`. `value` only sees
+    /// the first item of each group; later items are skipped after the key
+    /// lookup.
+    pub fn associate_with<V, F>(mut self, mut value: F) -> HashMap<K, V>
+    where
+        F: FnMut(&K, &I::Item) -> V,
+    {
+        let mut associated = HashMap::with_capacity(self.initial_capacity());
+
+        for (key, item) in self.by_ref() {
+            if let Entry::Vacant(entry) = associated.entry(key) {
+                let associated_value = value(entry.key(), &item);
+
+                entry.insert(associated_value);
+            }
+        }
+
+        associated
+    }
+
+    /// Counts the distinct keys among the first `sample_size` items, without
+    /// extrapolating: a lower bound on the real number of groups, exact when
+    /// the sample covers the source. Run it on a clone to size the real
+    /// aggregation with [`with_expected_groups`](Grouping::with_expected_groups).
+    pub fn estimate_cardinality(self, sample_size: usize) -> usize {
+        self.take(sample_size)
+            .map(|(key, _)| key)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    pub fn each_count(mut self) -> HashMap<K, usize> {
+        // Counts are bumped in place instead of going through `aggregate`,
+        // which would move every counter out of the map and back.
+        let mut counts = HashMap::with_capacity(self.initial_capacity());
+
+        for (key, _) in self.by_ref() {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
+    pub fn aggregate_bounded<R, O>(
+        self,
+        max_keys: usize,
+        operation: O,
+    ) -> Result<HashMap<K, R>, KeyLimitExceeded<K>>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+        K: Clone,
+    {
+        self.aggregate_bounded_with_policy(max_keys, OverflowPolicy::Error, operation)
+    }
+
+    pub fn aggregate_bounded_with_policy<R, O>(
+        mut self,
+        max_keys: usize,
+        policy: OverflowPolicy<K>,
+        mut operation: O,
+    ) -> Result<HashMap<K, R>, KeyLimitExceeded<K>>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+        K: Clone,
+    {
+        let mut m = HashMap::with_capacity(self.initial_capacity().min(max_keys));
+        let mut distinct_keys = 0;
+
+        let mut accumulate = |entry: Entry<'_, K, R>, value| match entry {
+            Entry::Occupied(entry) => {
+                maps::update_entry(entry, |key, accumulator| {
+                    operation(key, Some(accumulator), value)
+                });
+            }
+            Entry::Vacant(entry) => {
+                let value = operation(entry.key(), None, value);
+
+                entry.insert(value);
+            }
+        };
+
+        for (key, value) in self.by_ref() {
+            match m.entry(key) {
+                Entry::Vacant(entry) if distinct_keys == max_keys => match &policy {
+                    OverflowPolicy::Error => {
+                        return Err(KeyLimitExceeded {
+                            max_keys,
+                            key: entry.into_key(),
+                        })
+                    }
+                    OverflowPolicy::DropNew => {}
+                    OverflowPolicy::Other(other) => accumulate(m.entry(other.clone()), value),
+                },
+                entry => {
+                    if let Entry::Vacant(_) = entry {
+                        distinct_keys += 1;
+                    }
+
+                    accumulate(entry, value);
+                }
+            }
+        }
+
+        Ok(m)
+    }
+
+    /// Gini coefficient of the group sizes: `0.0` when every group has the
+    /// same number of items, approaching `1.0` as they get more skewed. A
+    /// single group or an empty source yields `0.0`.
+    pub fn gini_coefficient(self) -> f64 {
+        let mut counts = self.each_count().into_values().collect::<Vec<_>>();
+
+        if counts.len() < 2 {
+            return 0.0;
+        }
+
+        counts.sort_unstable();
+
+        let n = counts.len() as f64;
+        let total = counts.iter().sum::<usize>() as f64;
+        let weighted = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (i + 1) as f64 * count as f64)
+            .sum::<f64>();
+
+        2.0 * weighted / (n * total) - (n + 1.0) / n
+    }
+
+    /// Inner-joins runs of equal keys against `other` with a linear merge.
+    /// Both this grouping's source and `other` must already be sorted by key;
+    /// each run is aggregated with `operation` and paired with every entry of
+    /// `other` sharing its key.
+    pub fn merge_join_sorted<B, R, J, O>(self, other: J, mut operation: O) -> Vec<(K, R, B)>
+    where
+        J: IntoIterator<Item = (K, B)>,
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+        K: Ord,
+        R: Clone,
+    {
+        let mut runs = self.peekable();
+        let mut other = other.into_iter().peekable();
+        let mut joined = Vec::new();
+
+        while let Some((key, item)) = runs.next() {
+            let mut accumulator = operation(&key, None, item);
+
+            while let Some((_, item)) = runs.next_if(|(next, _)| *next == key) {
+                accumulator = operation(&key, Some(accumulator), item);
+            }
+
+            while other.next_if(|(next, _)| *next < key).is_some() {}
+
+            while let Some((key, value)) = other.next_if(|(next, _)| *next == key) {
+                joined.push((key, accumulator.clone(), value));
+            }
+        }
+
+        joined
+    }
+
+    /// The items with the smallest and largest projected time per group.
+    /// Both ends are kept, so `I::Item: Clone` is required: a single-item group
+    /// yields `(x, x.clone())`. On equal times the first end keeps the earliest
+    /// item seen and the last end the latest.
+    pub fn first_last_by<B, F>(self, mut time: F) -> HashMap<K, (I::Item, I::Item)>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord + Clone,
+        I::Item: Clone,
+    {
+        self.aggregate(|_, accumulator, item| {
+            let item_time = time(&item);
+
+            match accumulator {
+                None => ((item_time.clone(), item.clone()), (item_time, item)),
+                Some((first, last)) => {
+                    if item_time < first.0 {
+                        ((item_time, item), last)
+                    } else if item_time >= last.0 {
+                        (first, (item_time, item))
+                    } else {
+                        (first, last)
+                    }
+                }
+            }
+        })
+        .into_iter()
+        .map(|(key, ((_, first), (_, last)))| (key, (first, last)))
+        .collect()
+    }
+
+    /// The item with the largest projected value per group. Ties keep the
+    /// item seen first.
+    pub fn max_by<B, F>(self, selector: F) -> HashMap<K, I::Item>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+    {
+        self.max_by_with_tiebreak(selector, |_, _| Ordering::Equal)
+    }
+
+    /// The item with the smallest projected value per group. Ties keep the
+    /// item seen first.
+    pub fn min_by<B, F>(self, selector: F) -> HashMap<K, I::Item>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+    {
+        self.min_by_with_tiebreak(selector, |_, _| Ordering::Equal)
+    }
+
+    /// Like [`max_by`](Self::max_by), but items with equal projections are
+    /// ordered by `tiebreak` and the greater one wins. Items that are still
+    /// equal keep the one seen first.
+    pub fn max_by_with_tiebreak<B, F, T>(self, selector: F, tiebreak: T) -> HashMap<K, I::Item>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+        T: FnMut(&I::Item, &I::Item) -> Ordering,
+    {
+        Self::drop_values(self.extreme_by(selector, tiebreak, Ordering::Greater))
+    }
+
+    /// Like [`min_by`](Self::min_by), but items with equal projections are
+    /// ordered by `tiebreak` and the lesser one wins. Items that are still
+    /// equal keep the one seen first.
+    pub fn min_by_with_tiebreak<B, F, T>(self, selector: F, tiebreak: T) -> HashMap<K, I::Item>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+        T: FnMut(&I::Item, &I::Item) -> Ordering,
+    {
+        Self::drop_values(self.extreme_by(selector, tiebreak, Ordering::Less))
+    }
+
+    /// The largest projected value per group together with the item that
+    /// produced it. Ties keep the item seen first.
+    pub fn max_of_with_item<B, F>(self, selector: F) -> HashMap<K, (B, I::Item)>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+    {
+        self.extreme_by(selector, |_, _| Ordering::Equal, Ordering::Greater)
+    }
+
+    /// The smallest projected value per group together with the item that
+    /// produced it. Ties keep the item seen first.
+    pub fn min_of_with_item<B, F>(self, selector: F) -> HashMap<K, (B, I::Item)>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+    {
+        self.extreme_by(selector, |_, _| Ordering::Equal, Ordering::Less)
+    }
+
+    fn drop_values<B>(extremes: HashMap<K, (B, I::Item)>) -> HashMap<K, I::Item> {
+        extremes
+            .into_iter()
+            .map(|(key, (_, item))| (key, item))
+            .collect()
+    }
+
+    fn extreme_by<B, F, T>(
+        self,
+        mut selector: F,
+        mut tiebreak: T,
+        wins: Ordering,
+    ) -> HashMap<K, (B, I::Item)>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+        T: FnMut(&I::Item, &I::Item) -> Ordering,
+    {
+        self.aggregate(|_, accumulator: Option<(B, I::Item)>, item| {
+            let value = selector(&item);
+
+            match accumulator {
+                Some((best_value, best))
+                    if value.cmp(&best_value).then_with(|| tiebreak(&item, &best)) != wins =>
+                {
+                    (best_value, best)
+                }
+                _ => (value, item),
+            }
+        })
+    }
+
+    pub fn cross_tab<K2, Ks2>(self, mut inner_selector: Ks2) -> HashMap<K, HashMap<K2, usize>>
+    where
+        Ks2: FnMut(&I::Item) -> K2,
+        K2: Eq + Hash,
+    {
+        self.fold_with(HashMap::new, |_, mut accumulator, item| {
+            *accumulator.entry(inner_selector(&item)).or_insert(0) += 1;
+            accumulator
+        })
+    }
+
+    /// The [`cross_tab`](Self::cross_tab) counts as a sparse matrix, e.g. to
+    /// build a CSR matrix from: row keys, column keys (both in first-seen
+    /// order) and the nonzero `(row, column, count)` triplets in row-major
+    /// order.
+    #[allow(clippy::type_complexity)]
+    pub fn to_sparse_triplets<K2, Ks2>(
+        self,
+        mut inner_selector: Ks2,
+    ) -> (Vec<K>, Vec<K2>, Vec<(usize, usize, usize)>)
+    where
+        Ks2: FnMut(&I::Item) -> K2,
+        K2: Eq + Hash,
+    {
+        let mut columns = HashMap::new();
+        let mut row_count = 0;
+        let mut rows = self
+            .aggregate(
+                |_, accumulator: Option<(usize, HashMap<usize, usize>)>, item| {
+                    let (row, mut counts) = accumulator.unwrap_or_else(|| {
+                        row_count += 1;
+                        (row_count - 1, HashMap::new())
+                    });
+                    let column_count = columns.len();
+                    let column = *columns.entry(inner_selector(&item)).or_insert(column_count);
+
+                    *counts.entry(column).or_insert(0) += 1;
+                    (row, counts)
+                },
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        rows.sort_unstable_by_key(|(_, (row, _))| *row);
+
+        let mut columns = columns.into_iter().collect::<Vec<_>>();
+
+        columns.sort_unstable_by_key(|(_, column)| *column);
+
+        let mut triplets = Vec::new();
+        let rows = rows
+            .into_iter()
+            .map(|(key, (row, counts))| {
+                let start = triplets.len();
+
+                triplets.extend(
+                    counts
+                        .into_iter()
+                        .map(|(column, count)| (row, column, count)),
+                );
+                triplets[start..].sort_unstable();
+                key
+            })
+            .collect();
+
+        (
+            rows,
+            columns.into_iter().map(|(key, _)| key).collect(),
+            triplets,
+        )
+    }
+
+    /// Pearson's chi-square statistic of the contingency table built by
+    /// [`cross_tab`](Self::cross_tab), with expected counts derived from the
+    /// row and column margins. Empty sources yield `0.0`.
+    pub fn chi_square<K2, Ks2>(self, inner_selector: Ks2) -> f64
+    where
+        Ks2: FnMut(&I::Item) -> K2,
+        K2: Eq + Hash,
+    {
+        let table = self.cross_tab(inner_selector);
+        let mut column_totals: HashMap<&K2, usize> = HashMap::new();
+
+        for row in table.values() {
+            for (column, count) in row {
+                *column_totals.entry(column).or_insert(0) += count;
+            }
+        }
+
+        let total = column_totals.values().sum::<usize>() as f64;
+        let mut statistic = 0.0;
+
+        for row in table.values() {
+            let row_total = row.values().sum::<usize>() as f64;
+
+            for (column, &column_total) in &column_totals {
+                let observed = row.get(*column).copied().unwrap_or(0) as f64;
+                let expected = row_total * column_total as f64 / total;
+
+                statistic += (observed - expected).powi(2) / expected;
+            }
+        }
+
+        statistic
+    }
+
+    /// The smallest projected value per group. Values incomparable with the
+    /// current minimum (`partial_cmp` returns `None`, e.g. NaN) are skipped.
+    pub fn min_of<N, F>(self, mut selector: F) -> HashMap<K, N>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: PartialOrd,
+    {
+        self.aggregate(|_, accumulator, item| {
+            keep_extreme(accumulator, selector(&item), Ordering::Less)
+        })
+    }
+
+    /// The largest projected value per group. Values incomparable with the
+    /// current maximum (`partial_cmp` returns `None`, e.g. NaN) are skipped.
+    pub fn max_of<N, F>(self, mut selector: F) -> HashMap<K, N>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: PartialOrd,
+    {
+        self.aggregate(|_, accumulator, item| {
+            keep_extreme(accumulator, selector(&item), Ordering::Greater)
+        })
+    }
+
+    pub fn sum_of<N, F>(self, mut selector: F) -> HashMap<K, N>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: Add<Output = N>,
+    {
+        self.aggregate(|_, accumulator, item| {
+            let value = selector(&item);
+
+            match accumulator {
+                Some(sum) => sum + value,
+                None => value,
+            }
+        })
+    }
+
+    /// Element count and [`sum_of`](Self::sum_of) of each group in a single
+    /// pass, e.g. to derive averages of non-float values afterwards.
+    pub fn count_and_sum_of<N, F>(self, mut selector: F) -> HashMap<K, (usize, N)>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: Add<Output = N>,
+    {
+        self.aggregate(|_, accumulator, item| {
+            let value = selector(&item);
+
+            match accumulator {
+                Some((count, sum)) => (count + 1, sum + value),
+                None => (1, value),
+            }
+        })
+    }
+
+    pub fn average_of<N, F>(self, mut selector: F) -> HashMap<K, f64>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: Into<f64>,
+    {
+        self.aggregate(|_, accumulator: Option<(f64, usize)>, item| {
+            let (sum, count) = accumulator.unwrap_or_default();
+
+            (sum + selector(&item).into(), count + 1)
+        })
+        .into_iter()
+        .map(|(key, (sum, count))| (key, sum / count as f64))
+        .collect()
+    }
+
+    /// `exp(mean(ln(x)))` per group, e.g. the average of growth rates. Groups
+    /// containing a non-positive value yield NaN; single-item groups yield
+    /// their value unchanged.
+    pub fn geometric_mean_of<N, F>(self, mut selector: F) -> HashMap<K, f64>
+    where
+        F: FnMut(&I::Item) -> N,
+        N: Into<f64>,
+    {
+        self.aggregate(|_, accumulator: Option<(f64, f64, usize)>, item| {
+            let value = selector(&item).into();
+            let ln = if value > 0.0 { value.ln() } else { f64::NAN };
+
+            match accumulator {
+                Some((first, ln_sum, count)) => (first, ln_sum + ln, count + 1),
+                None => (value, ln, 1),
+            }
+        })
+        .into_iter()
+        .map(|(key, (first, ln_sum, count))| {
+            let mean = match count {
+                1 if ln_sum.is_nan() => f64::NAN,
+                1 => first,
+                _ => (ln_sum / count as f64).exp(),
+            };
+
+            (key, mean)
+        })
+        .collect()
+    }
+
+    /// Time between the earliest and the latest timestamp of each group, e.g.
+    /// how long each session lasted. Single-element groups span
+    /// [`Duration::ZERO`]; timestamps never make a span negative, whatever
+    /// order they arrive in.
+    pub fn time_span_of<F>(self, mut time: F) -> HashMap<K, Duration>
+    where
+        F: FnMut(&I::Item) -> SystemTime,
+    {
+        self.aggregate(|_, accumulator: Option<(SystemTime, SystemTime)>, item| {
+            let time = time(&item);
+
+            match accumulator {
+                Some((earliest, latest)) => (earliest.min(time), latest.max(time)),
+                None => (time, time),
+            }
+        })
+        .into_iter()
+        .map(|(key, (earliest, latest))| {
+            let span = latest.duration_since(earliest).unwrap_or(Duration::ZERO);
+
+            (key, span)
+        })
+        .collect()
+    }
+
+    /// The value with the highest total weight in each group. On equal totals
+    /// the value seen first in the group wins.
+    pub fn weighted_mode_of<V, F, W>(self, mut value: F, mut weight: W) -> HashMap<K, V>
+    where
+        F: FnMut(&I::Item) -> V,
+        W: FnMut(&I::Item) -> f64,
+        V: Eq + Hash,
+    {
+        self.fold_with(
+            HashMap::new,
+            |_, mut totals: HashMap<V, (usize, f64)>, item| {
+                let first_seen = totals.len();
+                let total = totals.entry(value(&item)).or_insert((first_seen, 0.0));
+
+                total.1 += weight(&item);
+                totals
+            },
+        )
+        .into_iter()
+        .filter_map(|(key, totals)| {
+            let (mode, _) = totals
+                .into_iter()
+                .max_by(|(_, a), (_, b)| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+            Some((key, mode))
+        })
+        .collect()
+    }
+
+    /// Sample covariance of two projections per group, accumulated in one
+    /// pass with Welford-style co-moment updates. Single-item groups yield
+    /// `0.0`.
+    pub fn covariance_of<F, G>(self, mut x: F, mut y: G) -> HashMap<K, f64>
+    where
+        F: FnMut(&I::Item) -> f64,
+        G: FnMut(&I::Item) -> f64,
+    {
+        self.aggregate(|_, accumulator: Option<CoMoments>, item| {
+            let mut moments = accumulator.unwrap_or_default();
+            moments.push(x(&item), y(&item));
+            moments
+        })
+        .into_iter()
+        .map(|(key, moments)| (key, moments.covariance()))
+        .collect()
+    }
+
+    /// Pearson's correlation coefficient of two projections per group, from
+    /// the same one-pass moments as [`covariance_of`](Self::covariance_of).
+    /// Groups where either projection has zero variance, including
+    /// single-item groups, yield `NaN`.
+    pub fn correlation_of<F, G>(self, mut x: F, mut y: G) -> HashMap<K, f64>
+    where
+        F: FnMut(&I::Item) -> f64,
+        G: FnMut(&I::Item) -> f64,
+    {
+        self.aggregate(|_, accumulator: Option<CoMoments>, item| {
+            let mut moments = accumulator.unwrap_or_default();
+            moments.push(x(&item), y(&item));
+            moments
+        })
+        .into_iter()
+        .map(|(key, moments)| (key, moments.correlation()))
+        .collect()
+    }
+
+    /// Yields, for every item in source order, its key and the population
+    /// standard deviation of its group so far (Welford's online update), so
+    /// a group's first item yields `0.0`.
+    pub fn running_std_of<F>(self, mut selector: F) -> impl Iterator<Item = (K, f64)>
+    where
+        F: FnMut(&I::Item) -> f64,
+        K: Clone,
+    {
+        let mut moments: HashMap<K, (usize, f64, f64)> = HashMap::new();
+
+        self.map(move |(key, item)| {
+            let value = selector(&item);
+            let (count, mean, m2) = moments.entry(key.clone()).or_insert((0, 0.0, 0.0));
+
+            *count += 1;
+
+            let delta = value - *mean;
+
+            *mean += delta / *count as f64;
+            *m2 += delta * (value - *mean);
+
+            let std = (*m2 / *count as f64).sqrt();
+
+            (key, std)
+        })
+    }
+
+    /// Yields every group's new accumulator right after each item, e.g. to
+    /// push live updates. The last value yielded per key equals what
+    /// [`aggregate`](Self::aggregate) returns for it.
+    pub fn scan_aggregate<R, O>(self, mut operation: O) -> impl Iterator<Item = (K, R)>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+        K: Clone,
+        R: Clone,
+    {
+        let mut accumulators: HashMap<K, Option<R>> = HashMap::new();
+
+        self.map(move |(key, item)| {
+            let accumulator = accumulators.entry(key.clone()).or_insert(None);
+            let value = operation(&key, accumulator.take(), item);
+
+            *accumulator = Some(value.clone());
+            (key, value)
+        })
+    }
+
+    /// Collects every group without its first item, e.g. to strip per-section
+    /// header rows. Single-item groups stay present with an empty `Vec`.
+    pub fn skip_first_within(self) -> HashMap<K, Vec<I::Item>> {
+        self.aggregate(
+            |_, accumulator: Option<Vec<I::Item>>, item| match accumulator {
+                Some(mut rest) => {
+                    rest.push(item);
+                    rest
+                }
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Counts items per bucket in each group, e.g. hour-of-day activity with
+    /// `N = 24`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` returns an index outside `0..N`.
+    pub fn histogram_within<const N: usize, F>(self, mut bucket: F) -> HashMap<K, [usize; N]>
+    where
+        F: FnMut(&I::Item) -> usize,
+    {
+        self.fold_with(
+            || [0; N],
+            |_, mut histogram, item| {
+                let index = bucket(&item);
+
+                assert!(index < N, "bucket index {} out of range 0..{}", index, N);
+                histogram[index] += 1;
+                histogram
+            },
+        )
+    }
+
+    /// Counts the character n-grams of each group. N-grams are taken per
+    /// item and never span two items; items shorter than `n` characters add
+    /// nothing, though their group is still present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn ngram_counts_within(self, n: usize) -> HashMap<K, HashMap<String, usize>>
+    where
+        I::Item: AsRef<str>,
+    {
+        assert!(n > 0, "n-grams must be at least one character long");
+
+        let mut boundaries = Vec::new();
+
+        self.fold_with(HashMap::new, |_, mut counts, item| {
+            let text = item.as_ref();
+
+            boundaries.clear();
+            boundaries.extend(text.char_indices().map(|(index, _)| index));
+            boundaries.push(text.len());
+
+            for window in boundaries.windows(n + 1) {
+                *counts
+                    .entry(text[window[0]..window[n]].to_owned())
+                    .or_insert(0) += 1;
+            }
+
+            counts
+        })
+    }
+
+    pub fn index_groups(self) -> HashMap<K, Vec<usize>> {
+        let mut index = 0;
+
+        self.fold_with(Vec::new, |_, mut accumulator, _| {
+            accumulator.push(index);
+            index += 1;
+            accumulator
+        })
+    }
+
+    /// Collects each group in encounter order, skipping items whose
+    /// projection was already seen in that group, e.g. ordered unique tags
+    /// per post.
+    pub fn collect_ordered_unique_within<V, F>(self, mut projection: F) -> HashMap<K, Vec<I::Item>>
+    where
+        F: FnMut(&I::Item) -> V,
+        V: Eq + Hash,
+    {
+        self.aggregate(|_, accumulator: Option<(HashSet<V>, Vec<I::Item>)>, item| {
+            let (mut seen, mut items) = accumulator.unwrap_or_default();
+
+            if seen.insert(projection(&item)) {
+                items.push(item);
+            }
+
+            (seen, items)
+        })
+        .into_iter()
+        .map(|(key, (_, items))| (key, items))
+        .collect()
+    }
+
+    /// Collects each group until `stop` returns `true` for it. `stop` sees the
+    /// items collected so far and is asked before each new item is added;
+    /// once it has fired, the group stays closed and later items are dropped.
+    pub fn collect_until<P>(self, mut stop: P) -> HashMap<K, Vec<I::Item>>
+    where
+        P: FnMut(&K, &[I::Item]) -> bool,
+    {
+        self.aggregate(|key, accumulator: Option<(bool, Vec<I::Item>)>, item| {
+            let (stopped, mut items) = accumulator.unwrap_or_default();
+
+            if stopped || stop(key, &items) {
+                return (true, items);
+            }
+
+            items.push(item);
+            (false, items)
+        })
+        .into_iter()
+        .map(|(key, (_, items))| (key, items))
+        .collect()
+    }
+
+    pub fn into_group_map(self) -> HashMap<K, Vec<I::Item>> {
+        self.fold_with(Vec::new, |_, mut accumulator, item| {
+            accumulator.push(item);
+            accumulator
+        })
+    }
+
+    /// Like [`into_group_map`](Self::into_group_map), with each group's `Vec`
+    /// allocated for `capacity_for(key)` items up front, e.g. to preallocate
+    /// known hot keys.
+    pub fn collect_groups_with_group_capacity<F>(
+        self,
+        mut capacity_for: F,
+    ) -> HashMap<K, Vec<I::Item>>
+    where
+        F: FnMut(&K) -> usize,
+    {
+        self.aggregate(|key, accumulator, item| {
+            let mut items = accumulator.unwrap_or_else(|| Vec::with_capacity(capacity_for(key)));
+
+            items.push(item);
+            items
+        })
+    }
+
+    /// Like [`into_group_map`](Self::into_group_map), but counts a clone of
+    /// the source first so every group `Vec` is allocated at its exact size.
+    /// Worth it when the source is cheap to replay (slices, ranges) and the
+    /// groups are large.
+    pub fn into_group_map_exact(self) -> HashMap<K, Vec<I::Item>>
+    where
+        I: Clone,
+        Ks: Clone,
+    {
+        // `derive(Clone)` would also demand `K: Clone`, so the counting pass
+        // is built by hand.
+        let counting = Grouping {
+            iter: self.iter.clone(),
+            key_selector: self.key_selector.clone(),
+            expected_groups: self.expected_groups,
+        };
+        let mut groups: HashMap<K, Vec<I::Item>> = counting
+            .each_count()
+            .into_iter()
+            .map(|(key, count)| (key, Vec::with_capacity(count)))
+            .collect();
+
+        for (key, item) in self {
+            groups.entry(key).or_default().push(item);
+        }
+
+        groups
+    }
+
+    /// Groups a borrowing iterator, e.g. `slice.iter()`, into vectors of
+    /// references that live as long as the source.
+    pub fn collect_ref_groups<'a, T>(self) -> HashMap<K, Vec<&'a T>>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a,
+    {
+        self.fold_with(Vec::new, |_, mut accumulator, item| {
+            accumulator.push(item);
+            accumulator
+        })
+    }
+
+    /// All groups as an owned `Vec` in the order their keys were first seen,
+    /// e.g. to hand them to `rayon`'s `into_par_iter` for per-group work.
+    pub fn into_groups_vec(self) -> Vec<(K, Vec<I::Item>)> {
+        let mut ordinal = 0;
+        let mut groups = self
+            .aggregate(|_, accumulator: Option<(usize, Vec<I::Item>)>, item| {
+                let (first_seen, mut items) = accumulator.unwrap_or_else(|| {
+                    ordinal += 1;
+                    (ordinal, Vec::new())
+                });
+
+                items.push(item);
+                (first_seen, items)
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        groups.sort_unstable_by_key(|(_, (first_seen, _))| *first_seen);
+        groups
+            .into_iter()
+            .map(|(key, (_, items))| (key, items))
+            .collect()
+    }
+
+    /// All groups, largest first. Groups of equal size keep the order in which
+    /// their keys were first seen.
+    pub fn collect_groups_by_frequency(self) -> Vec<(K, Vec<I::Item>)> {
+        let mut groups = self.into_groups_vec();
+
+        // Stable, so equal sizes stay in first-seen order.
+        groups.sort_by_key(|(_, items)| Reverse(items.len()));
+        groups
+    }
+
+    /// Sorts each group by descending score and pairs every item with its
+    /// 1-based standard competition rank: tied items share a rank and the
+    /// next rank skips accordingly (1, 2, 2, 4). Ties keep encounter order.
+    pub fn rank_within<B, F>(self, mut score: F) -> HashMap<K, Vec<(usize, I::Item)>>
+    where
+        F: FnMut(&I::Item) -> B,
+        B: Ord,
+    {
+        self.fold_with(Vec::new, |_, mut accumulator, item| {
+            accumulator.push((score(&item), item));
+            accumulator
+        })
+        .into_iter()
+        .map(|(key, mut scored)| {
+            scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+            let mut ranked: Vec<(usize, I::Item)> = Vec::with_capacity(scored.len());
+            let mut previous: Option<B> = None;
+
+            for (position, (item_score, item)) in scored.into_iter().enumerate() {
+                let rank = match (&previous, ranked.last()) {
+                    (Some(previous), Some((rank, _))) if *previous == item_score => *rank,
+                    _ => position + 1,
+                };
+
+                previous = Some(item_score);
+                ranked.push((rank, item));
+            }
+
+            (key, ranked)
+        })
+        .collect()
+    }
+}
+
+#[cfg(feature = "partition")]
+impl<I, Ks, K> Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: Eq + Hash,
+{
+    /// Streams every item into a writer for its key, e.g. one file per day.
+    /// Writers are created by `make_writer` on the first item of each key,
+    /// flushed at the end and returned. The first error from creating,
+    /// writing or flushing stops the partitioning.
+    pub fn write_partitioned<W, F, S>(
+        self,
+        mut make_writer: F,
+        mut serialize: S,
+    ) -> std::io::Result<HashMap<K, W>>
+    where
+        W: std::io::Write,
+        F: FnMut(&K) -> std::io::Result<W>,
+        S: FnMut(&mut W, I::Item) -> std::io::Result<()>,
+    {
+        let mut writers = HashMap::new();
+
+        for (key, item) in self {
+            let writer = match writers.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let writer = make_writer(entry.key())?;
+
+                    entry.insert(writer)
+                }
+            };
+
+            serialize(writer, item)?;
+        }
+
+        for writer in writers.values_mut() {
+            writer.flush()?;
+        }
+
+        Ok(writers)
+    }
+}
+
+#[cfg(feature = "fast-hash")]
+pub type FastMap<K, R> = HashMap<K, R, rustc_hash::FxBuildHasher>;
+
+#[cfg(feature = "fast-hash")]
+pub type FastCountMap<K> = FastMap<K, usize>;
+
+/// Terminals hashing with FxHash instead of SipHash: much faster for short
+/// keys such as small strings and integers, but not resistant to
+/// adversarial keys.
+#[cfg(feature = "fast-hash")]
+impl<I, Ks, K> Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: Eq + Hash,
+{
+    pub fn aggregate_fast<R, O>(self, operation: O) -> FastMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, I::Item) -> R,
+    {
+        self.aggregate_with_hasher(rustc_hash::FxBuildHasher, operation)
+    }
+
+    /// [`each_count`](Self::each_count) with FxHash, counting in place the
+    /// same way so the two only differ in their hasher.
+    pub fn each_count_fast(mut self) -> FastCountMap<K> {
+        let mut counts =
+            FastMap::with_capacity_and_hasher(self.initial_capacity(), rustc_hash::FxBuildHasher);
+
+        for (key, _) in self.by_ref() {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+
+        counts
+    }
+}
+
+#[cfg(feature = "prost")]
+impl<I, Ks, K> Grouping<I, Ks, K>
+where
+    I: Iterator,
+    Ks: FnMut(&I::Item) -> K,
+    K: Eq + Hash + fmt::Display,
+{
+    /// Encodes the [`each_count`](Self::each_count) result as a
+    /// [`GroupCounts`](crate::proto::GroupCounts) message, keyed by each
+    /// group key's `Display` output. Entries are sorted by that string so
+    /// equal counts always encode to the same bytes, and groups whose keys
+    /// display the same are summed into one entry.
+    pub fn to_prost_bytes(self) -> Vec<u8> {
+        use std::collections::BTreeMap;
+
+        use prost::Message;
+
+        use crate::proto::{GroupCount, GroupCounts};
+
+        let mut counts = BTreeMap::new();
+
+        for (key, count) in self.each_count() {
+            *counts.entry(key.to_string()).or_insert(0) += count as u64;
+        }
+
+        GroupCounts {
+            counts: counts
+                .into_iter()
+                .map(|(key, value)| GroupCount { key, value })
+                .collect(),
+        }
+        .encode_to_vec()
+    }
+}
+
+/// Folds `value` into the running extreme `current`, replacing it when `value`
+/// compares as `keep`. Values incomparable with the extreme are skipped, unless
+/// the extreme is itself incomparable, e.g. a NaN seen first.
+pub(crate) fn keep_extreme<N>(current: Option<N>, value: N, keep: Ordering) -> N
+where
+    N: PartialOrd,
+{
+    let current = match current {
+        Some(current) => current,
+        None => return value,
+    };
+
+    match value.partial_cmp(&current) {
+        Some(ordering) if ordering == keep => value,
+        Some(_) => current,
+        None if current.partial_cmp(&current).is_none() => value,
+        None => current,
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct CoMoments {
+    count: usize,
+    mean_x: f64,
+    mean_y: f64,
+    co_moment: f64,
+    m2_x: f64,
+    m2_y: f64,
+}
+
+impl CoMoments {
+    fn push(&mut self, x: f64, y: f64) {
+        self.count += 1;
+
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+        self.co_moment += dx * (y - self.mean_y);
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+    }
+
+    fn covariance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.co_moment / (self.count - 1) as f64
+        }
+    }
+
+    fn correlation(&self) -> f64 {
+        self.co_moment / (self.m2_x * self.m2_y).sqrt()
+    }
+}
+
+pub type PairKeySelector<A, B, K> = fn(&(A, B)) -> K;
+
+pub type InternedKeySelector<'a, T> = Box<dyn FnMut(&T) -> Symbol + 'a>;
+
+pub type DefaultingKeySelector<'ks, T, K> = Box<dyn FnMut(&T) -> K + 'ks>;
+
+#[cfg(feature = "unicode-normalization")]
+pub type NfcKeySelector<'ks, T> = Box<dyn FnMut(&T) -> String + 'ks>;
+
+pub type NamedGrouping<'r, I, K> =
+    Grouping<I, RegisteredKeySelector<'r, <I as Iterator>::Item, K>, K>;
+
+pub trait IntoGrouping<I>
+where
+    I: Iterator,
+{
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> Grouping<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K;
+
+    fn grouping_by_0<A, B>(self) -> Grouping<I, PairKeySelector<A, B, A>, A>
+    where
+        I: Iterator<Item = (A, B)>,
+        A: Clone + Eq + Hash;
+
+    fn grouping_by_1<A, B>(self) -> Grouping<I, PairKeySelector<A, B, B>, B>
+    where
+        I: Iterator<Item = (A, B)>,
+        B: Clone + Eq + Hash;
+
+    fn with_key_boundaries<Ks, K>(self, key_selector: Ks) -> KeyBoundaries<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: PartialEq + Clone;
+
+    /// Counts how often each key is immediately followed by each other key
+    /// (or itself) in source order. Sources with fewer than two items yield
+    /// an empty map.
+    fn transition_counts<Ks, K>(self, key_selector: Ks) -> HashMap<(K, K), usize>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: Eq + Hash + Clone;
+
+    /// Yields each run of equal consecutive keys as soon as it ends, holding
+    /// at most one group in memory. The source must be sorted (or at least
+    /// clustered) by key: a key that reappears after a different one starts
+    /// a new group instead of joining the earlier one.
+    fn streaming_groups_sorted<Ks, K>(self, key_selector: Ks) -> SortedGroups<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: PartialEq;
+
+    /// Groups on `normalize(key)` while keeping the first raw key seen for
+    /// every normalized key, e.g. to group tags case- and
+    /// whitespace-insensitively but still display one original spelling.
+    fn grouping_by_normalized<Ks, K, N, K2>(
+        self,
+        key_selector: Ks,
+        normalize: N,
+    ) -> NormalizedGrouping<I, Ks, K, N>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: Clone,
+        N: FnMut(K) -> K2,
+        K2: Eq + Hash;
+
+    /// Groups by keys interned in `interner`, so terminals hash and store a
+    /// [`Symbol`] instead of the key itself. The selector may borrow the key
+    /// from the item; it is only copied the first time the interner sees it.
+    fn grouping_by_interned<'a, Ks, Q, K>(
+        self,
+        key_selector: Ks,
+        interner: &'a mut KeyInterner<K>,
+    ) -> Grouping<I, InternedKeySelector<'a, I::Item>, Symbol>
+    where
+        Ks: FnMut(&I::Item) -> &Q + 'a,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+        K: Borrow<Q> + Eq + Hash + Clone;
+
+    /// Groups items whose selector returns `None` under `default`, e.g. to
+    /// route blank categories into an "unknown" bucket. Sentinel keys are
+    /// redirected by filtering them out in the selector:
+    /// `|item| Some(item.category).filter(|c| !c.is_empty())`.
+    fn grouping_by_or<'ks, Ks, K>(
+        self,
+        key_selector: Ks,
+        default: K,
+    ) -> Grouping<I, DefaultingKeySelector<'ks, I::Item, K>, K>
+    where
+        Ks: FnMut(&I::Item) -> Option<K> + 'ks,
+        K: Clone + 'ks;
+
+    /// Groups by the selector registered as `name` in `registry`.
+    fn grouping_by_name<'r, K>(
+        self,
+        name: &str,
+        registry: &'r KeySelectorRegistry<I::Item, K>,
+    ) -> Result<NamedGrouping<'r, I, K>, UnknownKeySelector>;
+
+    /// Groups by the NFC normalization of the selected string, so canonically
+    /// equivalent spellings (precomposed or combining marks) share a group.
+    #[cfg(feature = "unicode-normalization")]
+    fn grouping_by_nfc<'ks, Ks, S>(
+        self,
+        key_selector: Ks,
+    ) -> Grouping<I, NfcKeySelector<'ks, I::Item>, String>
+    where
+        Ks: FnMut(&I::Item) -> S + 'ks,
+        S: AsRef<str>;
+}
+
+impl<I> IntoGrouping<I> for I
+where
+    I: Iterator,
+{
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> Grouping<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K,
+    {
+        Grouping::new(self, key_selector)
+    }
+
+    fn grouping_by_0<A, B>(self) -> Grouping<I, PairKeySelector<A, B, A>, A>
+    where
+        I: Iterator<Item = (A, B)>,
+        A: Clone + Eq + Hash,
+    {
+        Grouping::new(self, |(a, _)| a.clone())
+    }
+
+    fn grouping_by_1<A, B>(self) -> Grouping<I, PairKeySelector<A, B, B>, B>
+    where
+        I: Iterator<Item = (A, B)>,
+        B: Clone + Eq + Hash,
+    {
+        Grouping::new(self, |(_, b)| b.clone())
+    }
+
+    fn with_key_boundaries<Ks, K>(self, key_selector: Ks) -> KeyBoundaries<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: PartialEq + Clone,
+    {
+        KeyBoundaries {
+            grouping: Grouping::new(self, key_selector),
+            previous: None,
+        }
+    }
+
+    fn transition_counts<Ks, K>(self, mut key_selector: Ks) -> HashMap<(K, K), usize>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: Eq + Hash + Clone,
+    {
+        let mut transitions = HashMap::new();
+        let mut previous: Option<K> = None;
+
+        for item in self {
+            let key = key_selector(&item);
+
+            if let Some(previous) = previous.replace(key.clone()) {
+                *transitions.entry((previous, key)).or_insert(0) += 1;
+            }
+        }
+
+        transitions
+    }
+
+    fn streaming_groups_sorted<Ks, K>(self, key_selector: Ks) -> SortedGroups<I, Ks, K>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: PartialEq,
+    {
+        SortedGroups {
+            grouping: Grouping::new(self, key_selector),
+            pending: None,
+        }
+    }
+
+    fn grouping_by_normalized<Ks, K, N, K2>(
+        self,
+        key_selector: Ks,
+        normalize: N,
+    ) -> NormalizedGrouping<I, Ks, K, N>
+    where
+        Ks: FnMut(&I::Item) -> K,
+        K: Clone,
+        N: FnMut(K) -> K2,
+        K2: Eq + Hash,
+    {
+        NormalizedGrouping {
+            grouping: Grouping::new(self, key_selector),
+            normalize,
+        }
+    }
+
+    fn grouping_by_interned<'a, Ks, Q, K>(
+        self,
+        mut key_selector: Ks,
+        interner: &'a mut KeyInterner<K>,
+    ) -> Grouping<I, InternedKeySelector<'a, I::Item>, Symbol>
+    where
+        Ks: FnMut(&I::Item) -> &Q + 'a,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+        K: Borrow<Q> + Eq + Hash + Clone,
+    {
+        Grouping::new(
+            self,
+            Box::new(move |item: &I::Item| interner.intern(key_selector(item))),
+        )
+    }
+
+    fn grouping_by_or<'ks, Ks, K>(
+        self,
+        mut key_selector: Ks,
+        default: K,
+    ) -> Grouping<I, DefaultingKeySelector<'ks, I::Item, K>, K>
+    where
+        Ks: FnMut(&I::Item) -> Option<K> + 'ks,
+        K: Clone + 'ks,
+    {
+        Grouping::new(
+            self,
+            Box::new(move |item: &I::Item| key_selector(item).unwrap_or_else(|| default.clone())),
+        )
+    }
+
+    fn grouping_by_name<'r, K>(
+        self,
+        name: &str,
+        registry: &'r KeySelectorRegistry<I::Item, K>,
+    ) -> Result<NamedGrouping<'r, I, K>, UnknownKeySelector> {
+        registry
+            .get(name)
+            .map(|key_selector| Grouping::new(self, key_selector))
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn grouping_by_nfc<'ks, Ks, S>(
+        self,
+        mut key_selector: Ks,
+    ) -> Grouping<I, NfcKeySelector<'ks, I::Item>, String>
+    where
+        Ks: FnMut(&I::Item) -> S + 'ks,
+        S: AsRef<str>,
+    {
+        use unicode_normalization::UnicodeNormalization;
+
+        Grouping::new(
+            self,
+            Box::new(move |item: &I::Item| key_selector(item).as_ref().nfc().collect()),
+        )
+    }
+}
+
+/// Lets any collection, not just an iterator, start a lazy grouping: `Vec`s,
+/// arrays, maps, ranges and `&[T]` group over their `IntoIter`, as if
+/// `.into_iter()` had been called first.
+///
+/// Every iterator is `IntoIterator` too, so import this trait instead of
+/// [`IntoGrouping`], not next to it, and not next to the eager
+/// [`IntoGrouping`](crate::eager::IntoGrouping) either: with both in scope,
+/// `grouping_by` is ambiguous.
+pub trait IntoGroupingExt: IntoIterator + Sized {
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> Grouping<Self::IntoIter, Ks, K>
+    where
+        Ks: FnMut(&Self::Item) -> K;
+}
+
+impl<C> IntoGroupingExt for C
+where
+    C: IntoIterator,
+{
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> Grouping<Self::IntoIter, Ks, K>
+    where
+        Ks: FnMut(&Self::Item) -> K,
+    {
+        Grouping::new(self.into_iter(), key_selector)
+    }
+}
+
+#[cfg(test)]
+// The original iteration and aggregate tests call `.into_iter()` on ranges.
+#[allow(clippy::useless_conversion)]
+mod test {
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        hash::{Hash, Hasher},
+        time::{Duration, SystemTime},
+    };
+
+    use super::{GroupRanges, IntoGrouping, KeyLimitExceeded, OverflowPolicy};
+
+    #[test]
+    fn test_grouping_iteration() {
+        let mut values = (0..10).into_iter().grouping_by(|i| *i % 3);
+
+        assert_eq!(values.next(), Some((0, 0)));
+        assert_eq!(values.next(), Some((1, 1)));
+        assert_eq!(values.next(), Some((2, 2)));
+        assert_eq!(values.next(), Some((0, 3)));
+        assert_eq!(values.next(), Some((1, 4)));
+        assert_eq!(values.next(), Some((2, 5)));
+        assert_eq!(values.next(), Some((0, 6)));
+        assert_eq!(values.next(), Some((1, 7)));
+        assert_eq!(values.next(), Some((2, 8)));
+        assert_eq!(values.next(), Some((0, 9)));
+        assert_eq!(values.next(), None);
+    }
+
+    #[test]
+    fn test_grouping_aggregate() {
+        let values = (3..=9).into_iter().grouping_by(|i| *i % 3);
+        let aggregated = values.aggregate(|key, accumulator: Option<String>, item| {
+            if let Some(mut accumulator) = accumulator {
+                accumulator.push_str(&format!("-{}", item));
+                accumulator
+            } else {
+                format!("{}:{}", key, item)
+            }
+        });
+
+        assert_eq!(
+            aggregated,
+            HashMap::from([
+                (0, "0:3-6-9".to_string()),
+                (1, "1:4-7".to_string()),
+                (2, "2:5-8".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_aggregate_hashes_once_per_element() {
+        thread_local! {
+            static HASHES: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(PartialEq, Eq)]
+        struct CountingKey(String);
+
+        impl Hash for CountingKey {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                HASHES.with(|hashes| hashes.set(hashes.get() + 1));
+                self.0.hash(state);
+            }
+        }
+
+        let words = "one two three four five six seven eight nine ten".split(' ');
+        let len = words.clone().count();
+        let totals = words
+            .grouping_by(|w| CountingKey(w.len().to_string()))
+            .aggregate(|_, accumulator, w| accumulator.unwrap_or(0) + w.len());
+        let hashes = HASHES.with(Cell::get);
+
+        // One entry lookup per element; accumulators are updated in place.
+        assert_eq!(totals.len(), 3);
+        assert_eq!(hashes, len);
+    }
+
+    #[test]
+    fn test_associate_with() {
+        let calls = Cell::new(0);
+        let users = [("tw", "ann"), ("jp", "ken"), ("tw", "bob"), ("jp", "yui")];
+        let first_user = users
+            .into_iter()
+            .grouping_by(|(country, _)| *country)
+            .associate_with(|country, (_, user)| {
+                calls.set(calls.get() + 1);
+                format!("{}:{}", country, user)
+            });
+
+        assert_eq!(calls.get(), 2);
+        assert_eq!(
+            first_user,
+            HashMap::from([("tw", "tw:ann".to_string()), ("jp", "jp:ken".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_estimate_cardinality() {
+        let grouping = (0..10_000u32).grouping_by(|n| n % 37);
+
+        assert_eq!(grouping.clone().estimate_cardinality(20), 20);
+        assert_eq!(grouping.clone().estimate_cardinality(500), 37);
+        assert_eq!(grouping.clone().estimate_cardinality(0), 0);
+
+        let estimate = grouping.clone().estimate_cardinality(1_000);
+
+        assert_eq!(
+            grouping.with_expected_groups(estimate).each_count().len(),
+            37
+        );
+    }
+
+    #[test]
+    fn test_with_expected_groups() {
+        let words = "one two three four five six seven eight nine ten".split(' ');
+        let unhinted = words.clone().grouping_by(|w| w.len()).each_count();
+        let hinted = words
+            .clone()
+            .grouping_by(|w| w.len())
+            .with_expected_groups(64);
+
+        assert_eq!(hinted.initial_capacity(), 64);
+        assert_eq!(hinted.each_count(), unhinted);
+        assert_eq!((0..10_000).grouping_by(|i| i % 7).initial_capacity(), 100);
+        assert_eq!(
+            (0..10_000_000).grouping_by(|i| i % 7).initial_capacity(),
+            1024
+        );
+        assert_eq!(
+            (0..10_000)
+                .grouping_by(|i| i % 7)
+                .with_expected_groups(7)
+                .then_by(|i| i % 2)
+                .initial_capacity(),
+            7
+        );
+        assert_eq!(
+            words
+                .grouping_by(|w| w.len())
+                .with_expected_groups(1)
+                .each_count(),
+            unhinted
+        );
+    }
+
+    #[test]
+    fn test_aggregate_batched() {
+        let batches = (0..25)
+            .grouping_by(|n| n % 2)
+            .aggregate_batched(10, |_, count: Option<usize>, _| count.unwrap_or(0) + 1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            batches,
+            [
+                HashMap::from([(0, 5), (1, 5)]),
+                HashMap::from([(0, 5), (1, 5)]),
+                HashMap::from([(0, 3), (1, 2)])
+            ]
+        );
+        assert_eq!(
+            (0..0)
+                .grouping_by(|n| n % 2)
+                .aggregate_batched(10, |_, _: Option<()>, _| ())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_aggregate_into() {
+        let sum = |_: &usize, accumulator: Option<usize>, word: &str| {
+            accumulator.unwrap_or(0) + word.len()
+        };
+        let mut totals = HashMap::new();
+
+        "one two three"
+            .split(' ')
+            .grouping_by(|w| w.len())
+            .aggregate_into(&mut totals, sum);
+
+        assert_eq!(totals, HashMap::from([(3, 6), (5, 5)]));
+
+        let seen = "four five six seven"
+            .split(' ')
+            .grouping_by(|w| w.len())
+            .aggregate_into(&mut totals, |key, accumulator, word| {
+                let existed = accumulator.is_some();
+
+                sum(key, accumulator, word) + if existed { 1000 } else { 0 }
+            });
+
+        assert_eq!(seen, &HashMap::from([(3, 1009), (4, 1008), (5, 1010)]));
+    }
+
+    #[cfg(feature = "partition")]
+    #[test]
+    fn test_write_partitioned() {
+        use std::io::Write;
+
+        let log = [
+            "2024-01-01 boot",
+            "2024-01-02 login",
+            "2024-01-01 login",
+            "2024-01-03 halt",
+            "2024-01-02 logout",
+        ];
+        let mut opened = Vec::new();
+        let files = log
+            .into_iter()
+            .grouping_by(|line| line[..10].to_string())
+            .write_partitioned(
+                |day| {
+                    opened.push(day.clone());
+                    Ok(Vec::new())
+                },
+                |file, line| writeln!(file, "{}", &line[11..]),
+            )
+            .unwrap();
+
+        assert_eq!(opened, ["2024-01-01", "2024-01-02", "2024-01-03"]);
+        assert_eq!(files["2024-01-01"], b"boot\nlogin\n");
+        assert_eq!(files["2024-01-02"], b"login\nlogout\n");
+        assert_eq!(files["2024-01-03"], b"halt\n");
+
+        let failed = log
+            .into_iter()
+            .grouping_by(|line| line.len())
+            .write_partitioned(
+                |_| Ok(Vec::new()),
+                |_, line| {
+                    if line.ends_with("halt") {
+                        Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"))
+                    } else {
+                        Ok(())
+                    }
+                },
+            );
+
+        assert_eq!(failed.unwrap_err().to_string(), "disk full");
+    }
+
+    #[cfg(feature = "prost")]
+    #[test]
+    fn test_to_prost_bytes() {
+        use std::fmt;
+
+        use prost::Message;
+
+        use crate::proto::{GroupCount, GroupCounts};
+
+        #[derive(PartialEq, Eq, Hash)]
+        struct Shard(u32);
+
+        impl fmt::Display for Shard {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "shard-{}", self.0 % 3)
+            }
+        }
+
+        let bytes = (0..100u32).grouping_by(|n| n % 3).to_prost_bytes();
+        let decoded = GroupCounts::decode(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            decoded.into_hash_map(),
+            HashMap::from([
+                ("0".to_string(), 34),
+                ("1".to_string(), 33),
+                ("2".to_string(), 33)
+            ])
+        );
+        assert!((0..8).all(|_| (0..100u32).grouping_by(|n| n % 3).to_prost_bytes() == bytes));
+        assert_eq!(
+            bytes,
+            [
+                0x0a, 0x05, 0x0a, 0x01, b'0', 0x10, 34, 0x0a, 0x05, 0x0a, 0x01, b'1', 0x10, 33,
+                0x0a, 0x05, 0x0a, 0x01, b'2', 0x10, 33
+            ]
+        );
+
+        let collided = (0..60u32).grouping_by(|n| Shard(n % 6)).to_prost_bytes();
+
+        assert_eq!(
+            GroupCounts::decode(collided.as_slice()).unwrap().counts,
+            [
+                GroupCount {
+                    key: "shard-0".to_string(),
+                    value: 20
+                },
+                GroupCount {
+                    key: "shard-1".to_string(),
+                    value: 20
+                },
+                GroupCount {
+                    key: "shard-2".to_string(),
+                    value: 20
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "fast-hash")]
+    #[test]
+    fn test_fast_hash_terminals() {
+        let words = "one two three four five six seven eight nine ten".split(' ');
+        let fast = words.clone().grouping_by(|w| w.len()).each_count_fast();
+        let default = words.clone().grouping_by(|w| w.len()).each_count();
+
+        assert_eq!(fast.len(), default.len());
+        assert!(default.iter().all(|(key, count)| fast[key] == *count));
+
+        let fast = words
+            .clone()
+            .grouping_by(|w| w.chars().next())
+            .aggregate_fast(|_, longest: Option<&str>, w| {
+                longest.filter(|l| l.len() >= w.len()).unwrap_or(w)
+            });
+        let default =
+            words
+                .grouping_by(|w| w.chars().next())
+                .aggregate(|_, longest: Option<&str>, w| {
+                    longest.filter(|l| l.len() >= w.len()).unwrap_or(w)
+                });
+
+        assert_eq!(fast.into_iter().collect::<HashMap<_, _>>(), default);
+    }
+
+    #[test]
+    fn test_aggregate_collecting_errors() {
+        let readings = [
+            ("a", "3"),
+            ("b", "1"),
+            ("a", "4"),
+            ("b", "x"),
+            ("b", "2"),
+            ("b", "-"),
+        ];
+        let totals = readings
+            .into_iter()
+            .grouping_by(|(sensor, _)| *sensor)
+            .aggregate_collecting_errors(|_, total: Option<u32>, (_, reading)| {
+                reading
+                    .parse::<u32>()
+                    .map(|reading| total.unwrap_or(0) + reading)
+                    .map_err(|_| reading)
+            });
+
+        assert_eq!(
+            totals,
+            HashMap::from([("a", Ok(7)), ("b", Err(vec!["x", "-"]))])
+        );
+    }
+
+    #[test]
+    fn test_fold_with_key() {
+        let fruits = vec![
+            "cherry",
+            "blueberry",
+            "citrus",
+            "apple",
+            "apricot",
+            "banana",
+            "coconut",
+        ]
+        .into_iter()
+        .grouping_by(|fruit_name| fruit_name.chars().next().unwrap());
+        let even_fruits = fruits.fold_with_key(
+            |_, _| vec![],
+            |_, mut accumulator, item| {
+                if item.len() % 2 == 0 {
+                    accumulator.push(item.to_string());
+                }
+
+                accumulator
+            },
+        );
+
+        assert_eq!(
+            even_fruits,
+            HashMap::from([
+                ('a', vec![]),
+                ('b', vec!["banana".to_string()]),
+                ('c', vec!["cherry".to_string(), "citrus".to_string()])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_weighted_mode_of() {
+        let sales = [
+            ("toys", "yoyo", 2.0),
+            ("toys", "drone", 120.0),
+            ("toys", "yoyo", 3.0),
+            ("toys", "yoyo", 2.5),
+            ("books", "atlas", 30.0),
+            ("books", "novel", 12.0),
+            ("books", "novel", 18.0),
+        ];
+        let grouping = sales.into_iter().grouping_by(|(category, _, _)| *category);
+        let by_count = grouping
+            .clone()
+            .weighted_mode_of(|(_, product, _)| *product, |_| 1.0);
+        let by_revenue =
+            grouping.weighted_mode_of(|(_, product, _)| *product, |(_, _, revenue)| *revenue);
+
+        assert_eq!(
+            by_count,
+            HashMap::from([("toys", "yoyo"), ("books", "novel")])
+        );
+        assert_eq!(
+            by_revenue,
+            HashMap::from([("toys", "drone"), ("books", "atlas")])
+        );
+    }
+
+    #[test]
+    fn test_count_and_sum_of() {
+        let orders = [
+            ("books", 12u32),
+            ("games", 60),
+            ("books", 8),
+            ("books", 25),
+            ("music", 0),
+        ];
+        let totals = orders
+            .into_iter()
+            .grouping_by(|(category, _)| *category)
+            .count_and_sum_of(|(_, price)| *price);
+
+        assert_eq!(
+            totals,
+            HashMap::from([("books", (3, 45)), ("games", (1, 60)), ("music", (1, 0))])
+        );
+    }
+
+    #[test]
+    fn test_geometric_mean_of() {
+        let growth = [
+            ("a", 1.1),
+            ("a", 1.5),
+            ("a", 0.9),
+            ("b", 2.5),
+            ("c", 4.0),
+            ("c", 0.0),
+        ];
+        let means = growth
+            .into_iter()
+            .grouping_by(|(cohort, _)| *cohort)
+            .geometric_mean_of(|(_, rate)| *rate);
+
+        assert!((means["a"] - (1.1f64 * 1.5 * 0.9).powf(1.0 / 3.0)).abs() < 1e-12);
+        assert!((means["a"] - 1.140_886).abs() < 1e-6);
+        assert_eq!(means["b"], 2.5);
+        assert!(means["c"].is_nan());
+    }
+
+    #[test]
+    fn test_time_span_of() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let events = [
+            ("alice", at(1_000)),
+            ("bob", at(1_200)),
+            ("alice", at(4_600)),
+            ("alice", at(900)),
+        ];
+        let spans = events
+            .into_iter()
+            .grouping_by(|(session, _)| *session)
+            .time_span_of(|(_, time)| *time);
+
+        assert_eq!(
+            spans,
+            HashMap::from([
+                ("alice", Duration::from_secs(3_700)),
+                ("bob", Duration::ZERO)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_covariance_of() {
+        let sales = [
+            ("tea", 1.0, 2.0),
+            ("tea", 2.0, 4.0),
+            ("tea", 3.0, 6.0),
+            ("tea", 4.0, 9.0),
+            ("pie", 5.0, 1.0),
+            ("pie", 1.0, 3.0),
+            ("jam", 3.0, 3.0),
+        ];
+        let covariance = sales
+            .into_iter()
+            .grouping_by(|(category, _, _)| *category)
+            .covariance_of(|(_, price, _)| *price, |(_, _, quantity)| *quantity);
+
+        assert!((covariance["tea"] - 11.5 / 3.0).abs() < 1e-12);
+        assert!((covariance["pie"] + 4.0).abs() < 1e-12);
+        assert_eq!(covariance["jam"], 0.0);
+    }
+
+    #[test]
+    fn test_correlation_of() {
+        let metrics = [
+            ("a", 1.0, 2.0),
+            ("a", 2.0, 4.0),
+            ("a", 3.0, 6.0),
+            ("a", 4.0, 9.0),
+            ("b", 1.0, 3.0),
+            ("b", 2.0, 2.0),
+            ("b", 3.0, 1.0),
+            ("c", 1.0, 5.0),
+            ("c", 2.0, 5.0),
+            ("d", 7.0, 7.0),
+        ];
+        let correlation = metrics
+            .into_iter()
+            .grouping_by(|(group, _, _)| *group)
+            .correlation_of(|(_, x, _)| *x, |(_, _, y)| *y);
+
+        // cov = 11.5 / 3, var(x) = 5 / 3, var(y) = 26.75 / 3
+        assert!((correlation["a"] - 11.5 / (5.0f64 * 26.75).sqrt()).abs() < 1e-12);
+        assert!((correlation["b"] + 1.0).abs() < 1e-12);
+        assert!(correlation["c"].is_nan());
+        assert!(correlation["d"].is_nan());
+    }
+
+    #[test]
+    fn test_running_std_of() {
+        let ticks = [
+            ("aapl", 10.0),
+            ("msft", 5.0),
+            ("aapl", 12.0),
+            ("aapl", 14.0),
+            ("msft", 5.0),
+        ];
+        let running = ticks
+            .into_iter()
+            .grouping_by(|(symbol, _)| *symbol)
+            .running_std_of(|(_, price)| *price)
+            .collect::<Vec<_>>();
+        let expected = [
+            ("aapl", 0.0),
+            ("msft", 0.0),
+            ("aapl", 1.0),
+            ("aapl", (8.0f64 / 3.0).sqrt()),
+            ("msft", 0.0),
+        ];
+
+        assert_eq!(running.len(), expected.len());
+
+        for ((symbol, std), (expected_symbol, expected_std)) in running.into_iter().zip(expected) {
+            assert_eq!(symbol, expected_symbol);
+            assert!((std - expected_std).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_scan_aggregate() {
+        let sum =
+            |_: &char, total: Option<u32>, (_, amount): (char, u32)| total.unwrap_or(0) + amount;
+        let payments = [('a', 5), ('b', 1), ('a', 2), ('b', 4), ('a', 1)];
+        let updates = payments
+            .into_iter()
+            .grouping_by(|(account, _)| *account)
+            .scan_aggregate(sum)
+            .collect::<Vec<_>>();
+
+        assert_eq!(updates, [('a', 5), ('b', 1), ('a', 7), ('b', 5), ('a', 8)]);
+        assert_eq!(
+            updates.into_iter().collect::<HashMap<_, _>>(),
+            payments
+                .into_iter()
+                .grouping_by(|(account, _)| *account)
+                .aggregate(sum)
+        );
+    }
+
+    #[test]
+    fn test_skip_first_within() {
+        let rows = [
+            ("a.csv", "id,name"),
+            ("b.csv", "id,total"),
+            ("a.csv", "1,ann"),
+            ("c.csv", "id"),
+            ("a.csv", "2,bob"),
+            ("b.csv", "1,9.5"),
+        ];
+        let bodies = rows
+            .into_iter()
+            .grouping_by(|(file, _)| *file)
+            .skip_first_within();
+
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies["a.csv"], [("a.csv", "1,ann"), ("a.csv", "2,bob")]);
+        assert_eq!(bodies["b.csv"], [("b.csv", "1,9.5")]);
+        assert!(bodies["c.csv"].is_empty());
+        assert_eq!(bodies.values().map(Vec::len).sum::<usize>(), rows.len() - 3);
+    }
+
+    #[test]
+    fn test_histogram_within() {
+        let logins = [("ann", 9), ("bob", 23), ("ann", 9), ("ann", 14), ("bob", 0)];
+        let activity = logins
+            .into_iter()
+            .grouping_by(|(user, _)| *user)
+            .histogram_within::<24, _>(|(_, hour)| *hour);
+
+        let mut ann = [0; 24];
+        ann[9] = 2;
+        ann[14] = 1;
+
+        let mut bob = [0; 24];
+        bob[0] = 1;
+        bob[23] = 1;
+
+        assert_eq!(activity, HashMap::from([("ann", ann), ("bob", bob)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "bucket index 4 out of range 0..4")]
+    fn test_histogram_within_out_of_range() {
+        (0..5).grouping_by(|_| ()).histogram_within::<4, _>(|i| *i);
+    }
+
+    #[test]
+    fn test_ngram_counts_within() {
+        let words = ["tat", "über", "at", "a"];
+        let bigrams = words
+            .into_iter()
+            .grouping_by(|word| if word.is_ascii() { "en" } else { "de" })
+            .ngram_counts_within(2);
+
+        assert_eq!(bigrams.len(), 2);
+        assert_eq!(
+            bigrams["en"],
+            HashMap::from([("ta".to_string(), 1), ("at".to_string(), 2)])
+        );
+        assert_eq!(
+            bigrams["de"],
+            HashMap::from([
+                ("üb".to_string(), 1),
+                ("be".to_string(), 1),
+                ("er".to_string(), 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_collect_ordered_unique_within() {
+        let tags = [
+            (1, "Rust"),
+            (2, "go"),
+            (1, "async"),
+            (1, "rust"),
+            (2, "Go"),
+            (1, "tokio"),
+            (1, "Async"),
+        ];
+        let unique = tags
+            .into_iter()
+            .grouping_by(|(post, _)| *post)
+            .collect_ordered_unique_within(|(_, tag)| tag.to_lowercase());
+
+        assert_eq!(
+            unique,
+            HashMap::from([
+                (1, vec![(1, "Rust"), (1, "async"), (1, "tokio")]),
+                (2, vec![(2, "go")])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_index_groups() {
+        let tokens = vec!["to", "be", "or", "not", "to", "be"];
+        let postings = tokens
+            .clone()
+            .into_iter()
+            .grouping_by(|t| *t)
+            .index_groups();
+
+        assert_eq!(
+            postings,
+            HashMap::from([
+                ("to", vec![0, 4]),
+                ("be", vec![1, 5]),
+                ("or", vec![2]),
+                ("not", vec![3])
+            ])
+        );
+
+        for (token, indices) in postings {
+            assert!(indices.into_iter().all(|index| tokens[index] == token));
+        }
+    }
+
+    #[test]
+    fn test_to_sparse_triplets() {
+        let purchases = [
+            ("ann", "book"),
+            ("bob", "pen"),
+            ("ann", "pen"),
+            ("ann", "book"),
+            ("cid", "ink"),
+            ("bob", "pen"),
+        ];
+        let (users, products, triplets) = purchases
+            .into_iter()
+            .grouping_by(|(user, _)| *user)
+            .to_sparse_triplets(|(_, product)| *product);
+
+        assert_eq!(users, ["ann", "bob", "cid"]);
+        assert_eq!(products, ["book", "pen", "ink"]);
+        assert_eq!(triplets, [(0, 0, 2), (0, 1, 1), (1, 1, 2), (2, 2, 1)]);
+    }
+
+    #[test]
+    fn test_chi_square() {
+        let observations = [
+            (("a", "x"), 10),
+            (("a", "y"), 20),
+            (("b", "x"), 30),
+            (("b", "y"), 40),
+        ]
+        .into_iter()
+        .flat_map(|(cell, count)| vec![cell; count]);
+        let table = observations
+            .clone()
+            .grouping_by(|(row, _)| *row)
+            .cross_tab(|(_, column)| *column);
+
+        assert_eq!(
+            table,
+            HashMap::from([
+                ("a", HashMap::from([("x", 10), ("y", 20)])),
+                ("b", HashMap::from([("x", 30), ("y", 40)]))
+            ])
+        );
+
+        let statistic = observations
+            .grouping_by(|(row, _)| *row)
+            .chi_square(|(_, column)| *column);
+
+        // 4/12 + 4/18 + 4/28 + 4/42
+        assert!((statistic - 0.793_650_8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_aggregate_bounded_with_policy() {
+        let keys = [0, 1, 2, 3, 0, 4, 1, 5];
+        let count = |_: &usize, accumulator: Option<usize>, _| accumulator.unwrap_or(0) + 1;
+
+        assert_eq!(
+            keys.into_iter()
+                .grouping_by(|k| *k)
+                .aggregate_bounded(3, count),
+            Err(KeyLimitExceeded {
+                max_keys: 3,
+                key: 3
+            })
+        );
+        assert_eq!(
+            keys.into_iter()
+                .grouping_by(|k| *k)
+                .aggregate_bounded_with_policy(3, OverflowPolicy::Error, count)
+                .unwrap_err()
+                .to_string(),
+            "key 3 exceeds the limit of 3 distinct keys"
+        );
+        assert_eq!(
+            keys.into_iter()
+                .grouping_by(|k| *k)
+                .aggregate_bounded_with_policy(3, OverflowPolicy::DropNew, count),
+            Ok(HashMap::from([(0, 2), (1, 2), (2, 1)]))
+        );
+        assert_eq!(
+            keys.into_iter()
+                .grouping_by(|k| *k)
+                .aggregate_bounded_with_policy(3, OverflowPolicy::Other(99), count),
+            Ok(HashMap::from([(0, 2), (1, 2), (2, 1), (99, 3)]))
+        );
+        assert_eq!(
+            keys.into_iter()
+                .grouping_by(|k| *k)
+                .aggregate_bounded(6, count),
+            Ok(keys.into_iter().grouping_by(|k| *k).each_count())
+        );
+    }
+
+    #[test]
+    fn test_gini_coefficient() {
+        let shards = [0, 1, 1, 2, 2, 2, 3, 3, 3, 3];
+        let gini = shards.into_iter().grouping_by(|s| *s).gini_coefficient();
+
+        // 2 * (1*1 + 2*2 + 3*3 + 4*4) / (4 * 10) - 5 / 4
+        assert!((gini - 0.25).abs() < 1e-12);
+        assert_eq!((0..9).grouping_by(|i| *i % 3).gini_coefficient(), 0.0);
+        assert_eq!((0..9).grouping_by(|_| 0).gini_coefficient(), 0.0);
+        assert_eq!((0..0).grouping_by(|i| *i).gini_coefficient(), 0.0);
+    }
+
+    #[test]
+    fn test_merge_join_sorted() {
+        let sales = [
+            ("east", 10),
+            ("east", 5),
+            ("north", 7),
+            ("west", 3),
+            ("west", 4),
+        ];
+        let regions = [
+            ("central", "Kansas"),
+            ("east", "Boston"),
+            ("west", "Denver"),
+        ];
+        let joined = sales
+            .into_iter()
+            .grouping_by(|(region, _)| *region)
+            .merge_join_sorted(regions, |_, total: Option<usize>, (_, amount)| {
+                total.unwrap_or(0) + amount
+            });
+
+        assert_eq!(joined, [("east", 15, "Boston"), ("west", 7, "Denver")]);
+    }
+
+    #[test]
+    fn test_collect_groups_with_group_capacity() {
+        let groups = (0..100u32)
+            .grouping_by(|n| if n % 10 == 0 { "cold" } else { "hot" })
+            .collect_groups_with_group_capacity(|key| if *key == "hot" { 128 } else { 0 });
+
+        assert_eq!(groups["hot"].len(), 90);
+        assert!(groups["hot"].capacity() >= 128);
+        assert_eq!(groups["cold"], [0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
+    }
+
+    #[test]
+    fn test_into_group_map_exact() {
+        let grouping = (0..1000u32).grouping_by(|n| n % 7);
+        let exact = grouping.clone().into_group_map_exact();
+
+        assert_eq!(exact, grouping.into_group_map());
+        assert_eq!(exact[&0].capacity(), 143);
+        assert!(exact.values().all(|group| group.capacity() == group.len()));
+    }
+
+    #[test]
+    fn test_into_groups_vec() {
+        let words = ["pear", "fig", "plum", "kiwi", "lime", "date", "apple"];
+        let groups = words.into_iter().grouping_by(|w| w.len()).into_groups_vec();
+
+        assert_eq!(
+            groups,
+            vec![
+                (4, vec!["pear", "plum", "kiwi", "lime", "date"]),
+                (3, vec!["fig"]),
+                (5, vec!["apple"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_ranges_sorted() {
+        let scores = [(1, 'a'), (1, 'b'), (2, 'c'), (4, 'd'), (4, 'e'), (4, 'f')];
+        let ranges = scores.group_ranges_sorted(|(level, _)| *level);
+
+        assert_eq!(ranges, [(1, 0..2), (2, 2..3), (4, 3..6)]);
+        assert_eq!(scores[ranges[2].1.clone()], [(4, 'd'), (4, 'e'), (4, 'f')]);
+        assert!([0u8; 0].group_ranges_sorted(|n| *n).is_empty());
+    }
+
+    #[test]
+    fn test_max_of_with_item() {
+        let requests = [
+            ("/login", "r1", 120),
+            ("/feed", "r2", 340),
+            ("/login", "r3", 480),
+            ("/feed", "r4", 340),
+            ("/login", "r5", 90),
+        ];
+        let grouping = requests
+            .into_iter()
+            .grouping_by(|(endpoint, _, _)| *endpoint);
+
+        assert_eq!(
+            grouping.clone().max_of_with_item(|(_, _, millis)| *millis),
+            HashMap::from([
+                ("/login", (480, ("/login", "r3", 480))),
+                ("/feed", (340, ("/feed", "r2", 340)))
+            ])
+        );
+        assert_eq!(
+            grouping.min_of_with_item(|(_, _, millis)| *millis)["/login"],
+            (90, ("/login", "r5", 90))
+        );
+    }
+
+    #[test]
+    fn test_grouping_by_or() {
+        let posts = [("rust", 1), ("", 2), ("go", 3), ("rust", 4), ("", 5)];
+        let by_category = posts
+            .into_iter()
+            .grouping_by_or(
+                |(category, _)| Some(*category).filter(|c| !c.is_empty()),
+                "unknown",
+            )
+            .each_count();
+
+        assert_eq!(
+            by_category,
+            HashMap::from([("rust", 2), ("go", 1), ("unknown", 2)])
+        );
+
+        let parsed = ["3", "x", "4", "3"]
+            .into_iter()
+            .grouping_by_or(|s| s.parse::<u8>().ok(), 0)
+            .each_count();
+
+        assert_eq!(parsed, HashMap::from([(3, 2), (4, 1), (0, 1)]));
+    }
+
+    #[test]
+    fn test_max_by_min_by() {
+        let requests = [
+            ("eu", 7, 120),
+            ("us", 3, 80),
+            ("eu", 2, 120),
+            ("us", 9, 40),
+            ("eu", 5, 30),
+            ("us", 1, 40),
+        ];
+        let grouping = requests.into_iter().grouping_by(|(region, _, _)| *region);
+        let latency = |(_, _, latency): &(&str, u32, u32)| *latency;
+        let id = |(_, id, _): &(&str, u32, u32)| *id;
+
+        assert_eq!(
+            grouping.clone().max_by(latency),
+            HashMap::from([("eu", ("eu", 7, 120)), ("us", ("us", 3, 80))])
+        );
+        assert_eq!(
+            grouping.clone().min_by(latency),
+            HashMap::from([("eu", ("eu", 5, 30)), ("us", ("us", 9, 40))])
+        );
+        assert_eq!(
+            grouping
+                .clone()
+                .max_by_with_tiebreak(latency, |a, b| id(b).cmp(&id(a))),
+            HashMap::from([("eu", ("eu", 2, 120)), ("us", ("us", 3, 80))])
+        );
+        assert_eq!(
+            grouping.min_by_with_tiebreak(latency, |a, b| id(a).cmp(&id(b))),
+            HashMap::from([("eu", ("eu", 5, 30)), ("us", ("us", 1, 40))])
+        );
+    }
+
+    #[test]
+    fn test_first_last_by() {
+        let events = [
+            ("s1", 5, "click"),
+            ("s2", 1, "open"),
+            ("s1", 2, "open"),
+            ("s1", 9, "close"),
+            ("s3", 4, "open"),
+            ("s1", 9, "logout"),
+            ("s2", 3, "close"),
+        ];
+        let sessions = events
+            .into_iter()
+            .grouping_by(|(session, _, _)| *session)
+            .first_last_by(|(_, time, _)| *time);
+
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions["s1"], (("s1", 2, "open"), ("s1", 9, "logout")));
+        assert_eq!(sessions["s2"], (("s2", 1, "open"), ("s2", 3, "close")));
+        assert_eq!(sessions["s3"], (("s3", 4, "open"), ("s3", 4, "open")));
+    }
+
+    #[test]
+    fn test_with_key_boundaries() {
+        let logs = [
+            (10, "boot"),
+            (10, "load"),
+            (11, "serve"),
+            (12, "serve"),
+            (12, "serve"),
+            (12, "halt"),
+        ];
+        let boundaries = logs
+            .into_iter()
+            .with_key_boundaries(|(minute, _)| *minute)
+            .map(|(boundary, minute, _)| (boundary, minute))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            boundaries,
+            [
+                (true, 10),
+                (false, 10),
+                (true, 11),
+                (true, 12),
+                (false, 12),
+                (false, 12)
+            ]
+        );
+        assert_eq!(
+            std::iter::empty::<i32>().with_key_boundaries(|i| *i).next(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_collect_until() {
+        let checks = Cell::new(0);
+        let groups = [
+            ("a", 4),
+            ("b", 1),
+            ("a", 7),
+            ("a", 2),
+            ("b", 3),
+            ("a", 9),
+            ("b", 8),
+        ]
+        .into_iter()
+        .grouping_by(|(key, _)| *key)
+        .collect_until(|_, items| {
+            checks.set(checks.get() + 1);
+            items.iter().map(|(_, value)| value).sum::<i32>() >= 10
+        });
+
+        assert_eq!(groups["a"], [("a", 4), ("a", 7)]);
+        assert_eq!(groups["b"], [("b", 1), ("b", 3), ("b", 8)]);
+        assert_eq!(checks.get(), 6);
+    }
+
+    #[test]
+    fn test_collect_ref_groups() {
+        #[derive(Debug, PartialEq)]
+        struct Record {
+            city: String,
+            id: u32,
+        }
+
+        let record = |city: &str, id| Record {
+            city: city.into(),
+            id,
+        };
+        let records = [record("Oslo", 1), record("Lima", 2), record("Oslo", 3)];
+        let index = records
+            .iter()
+            .grouping_by(|r| r.city.clone())
+            .collect_ref_groups();
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index["Oslo"], [&records[0], &records[2]]);
+        assert_eq!(index["Lima"], [&records[1]]);
+        assert!(std::ptr::eq(index["Lima"][0], &records[1]));
+    }
+
+    #[test]
+    fn test_collect_groups_by_frequency() {
+        let groups = ["b1", "a1", "c1", "a2", "c2", "d1", "a3", "e1"]
+            .into_iter()
+            .grouping_by(|s| s.chars().next().unwrap())
+            .collect_groups_by_frequency();
+
+        assert_eq!(
+            groups,
+            [
+                ('a', vec!["a1", "a2", "a3"]),
+                ('c', vec!["c1", "c2"]),
+                ('b', vec!["b1"]),
+                ('d', vec!["d1"]),
+                ('e', vec!["e1"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grouping_by_normalized() {
+        let tags = [" Tag ", "rust", "tag", "TAG", "Rust "]
+            .into_iter()
+            .grouping_by_normalized(|tag| tag.to_string(), |tag| tag.trim().to_lowercase())
+            .into_group_map();
+
+        assert_eq!(tags.len(), 2);
+        assert_eq!(
+            tags["tag"],
+            (" Tag ".to_string(), vec![" Tag ", "tag", "TAG"])
+        );
+        assert_eq!(tags["rust"], ("rust".to_string(), vec!["rust", "Rust "]));
+
+        let counts = ["a", "A", "b"]
+            .into_iter()
+            .grouping_by_normalized(|s| *s, |s| s.to_ascii_uppercase())
+            .each_count();
+
+        assert_eq!(
+            counts,
+            HashMap::from([("A".into(), ("a", 2)), ("B".into(), ("b", 1))])
+        );
+    }
+
+    #[test]
+    fn test_transition_counts() {
+        let journey = ["home", "shop", "shop", "cart", "shop", "cart", "home"];
+        let transitions = journey.into_iter().transition_counts(|page| *page);
+
+        assert_eq!(
+            transitions,
+            HashMap::from([
+                (("home", "shop"), 1),
+                (("shop", "shop"), 1),
+                (("shop", "cart"), 2),
+                (("cart", "shop"), 1),
+                (("cart", "home"), 1),
+            ])
+        );
+        assert!(["home"]
+            .into_iter()
+            .transition_counts(|page| *page)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_streaming_groups_sorted() {
+        let pulled = Cell::new(0);
+        let mut groups = [1, 1, 2, 3, 3, 3, 1]
+            .into_iter()
+            .inspect(|_| pulled.set(pulled.get() + 1))
+            .streaming_groups_sorted(|i| *i);
+
+        assert_eq!(groups.next(), Some((1, vec![1, 1])));
+        assert_eq!(pulled.get(), 3);
+        assert_eq!(groups.next(), Some((2, vec![2])));
+        assert_eq!(pulled.get(), 4);
+        assert_eq!(groups.next(), Some((3, vec![3, 3, 3])));
+        assert_eq!(pulled.get(), 7);
+        assert_eq!(groups.next(), Some((1, vec![1])));
+        assert_eq!(groups.next(), None);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_grouping_by_nfc() {
+        let names = ["Ren\u{e9}", "Rene\u{301}", "Rene", "ren\u{e9}"];
+        let counts = names
+            .into_iter()
+            .grouping_by_nfc(|name| name.to_string())
+            .each_count();
+
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts["Ren\u{e9}"], 2);
+        assert_eq!(counts["Rene"], 1);
+        assert_eq!(counts["ren\u{e9}"], 1);
+    }
+
+    #[test]
+    fn test_rank_within() {
+        let players = [
+            ("eu", "ana", 30),
+            ("na", "bob", 12),
+            ("eu", "cid", 45),
+            ("eu", "dee", 30),
+            ("na", "eve", 20),
+            ("eu", "fay", 10),
+        ];
+        let ranks = players
+            .into_iter()
+            .grouping_by(|(region, _, _)| *region)
+            .rank_within(|(_, _, score)| *score);
+        let names = |region| {
+            ranks[region]
+                .iter()
+                .map(|(rank, (_, name, _))| (*rank, *name))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranks.len(), 2);
+        assert_eq!(
+            names("eu"),
+            [(1, "cid"), (2, "ana"), (2, "dee"), (4, "fay")]
+        );
+        assert_eq!(names("na"), [(1, "eve"), (2, "bob")]);
+    }
+
+    #[test]
+    fn test_grouping_by_pair_element() {
+        let events = [
+            ("alice", "login"),
+            ("bob", "login"),
+            ("alice", "upload"),
+            ("alice", "logout"),
+        ];
+
+        assert_eq!(
+            events.into_iter().grouping_by_0().each_count(),
+            HashMap::from([("alice", 3), ("bob", 1)])
+        );
+        assert_eq!(
+            events
+                .into_iter()
+                .grouping_by_1()
+                .fold(vec![], |mut users, (user, _)| {
+                    users.push(user);
+                    users
+                }),
+            HashMap::from([
+                ("login", vec!["alice", "bob"]),
+                ("upload", vec!["alice"]),
+                ("logout", vec!["alice"])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_then_by() {
+        let timestamps = [
+            ("2024-01-01", 9),
+            ("2024-01-01", 9),
+            ("2024-01-01", 17),
+            ("2024-01-02", 9),
+            ("2024-01-02", 9),
+            ("2024-01-02", 9),
+        ];
+        let counts = timestamps
+            .into_iter()
+            .grouping_by(|(date, _)| *date)
+            .then_by(|(_, hour)| *hour)
+            .each_count();
+
+        assert_eq!(
+            counts,
+            HashMap::from([
+                (("2024-01-01", 9), 2),
+                (("2024-01-01", 17), 1),
+                (("2024-01-02", 9), 3)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_numeric_terminals() {
+        let numbers = || (1..=10u32).grouping_by(|i| *i % 3);
+
+        assert_eq!(
+            numbers().min_of(|i| *i),
+            HashMap::from([(0, 3), (1, 1), (2, 2)])
+        );
+        assert_eq!(
+            numbers().max_of(|i| *i),
+            HashMap::from([(0, 9), (1, 10), (2, 8)])
+        );
+        assert_eq!(
+            numbers().sum_of(|i| *i),
+            HashMap::from([(0, 18), (1, 22), (2, 15)])
+        );
+        assert_eq!(
+            numbers().average_of(|i| *i),
+            HashMap::from([(0, 6.0), (1, 5.5), (2, 5.0)])
+        );
+
+        let ties = || {
+            [(0, 'a'), (1, 'b'), (1, 'c'), (0, 'd')]
+                .into_iter()
+                .grouping_by(|_| ())
+        };
+
+        assert_eq!(
+            ties().min_of(|&(score, name)| (score, name)),
+            HashMap::from([((), (0, 'a'))])
+        );
+        assert_eq!(ties().max_of(|&(score, _)| score), HashMap::from([((), 1)]));
+
+        let readings = || {
+            [f64::NAN, 2.0, f64::NAN, 1.0, 3.0]
+                .into_iter()
+                .grouping_by(|_| ())
+        };
+
+        assert_eq!(readings().min_of(|r| *r), HashMap::from([((), 1.0)]));
+        assert_eq!(readings().max_of(|r| *r), HashMap::from([((), 3.0)]));
+    }
+
+    mod into_iterator {
+        use std::collections::HashMap;
+
+        use super::super::IntoGroupingExt;
+
+        #[test]
+        fn test_grouping_by_without_into_iter() {
+            let expected = HashMap::from([(0, 2), (1, 3)]);
+
+            assert_eq!(
+                vec![1, 2, 3, 4, 5].grouping_by(|n| n % 2).each_count(),
+                expected
+            );
+            assert_eq!(
+                [1, 2, 3, 4, 5].grouping_by(|n| n % 2).each_count(),
+                expected
+            );
+            assert_eq!((1..=5).grouping_by(|n| n % 2).each_count(), expected);
+            assert_eq!(
+                [1, 2, 3, 4, 5][..].grouping_by(|n| *n % 2).each_count(),
+                expected
+            );
+            assert_eq!(
+                HashMap::from([("a", 1), ("b", 2), ("c", 3)])
+                    .grouping_by(|(_, n)| n % 2)
+                    .each_count(),
+                HashMap::from([(0, 1), (1, 2)])
+            );
+        }
+    }
+}
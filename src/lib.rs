@@ -1,2 +1,37 @@
-pub mod akt1;
-pub mod akt2;
+pub mod assert;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod eager;
+#[cfg(test)]
+mod fixtures;
+pub mod grouped;
+pub mod intern;
+pub mod io;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod lazy;
+pub mod maps;
+#[cfg(feature = "rayon")]
+pub mod par;
+pub mod parallel;
+pub mod prelude;
+#[cfg(feature = "prost")]
+pub mod proto;
+pub mod registry;
+#[cfg(feature = "async")]
+pub mod stream;
+
+pub use eager::{Grouping as EagerGrouping, GroupingImpl, IntoGrouping as IntoEagerGrouping};
+pub use lazy::{Grouping as LazyGrouping, IntoGrouping as IntoLazyGrouping};
+
+/// Former name of [`eager`].
+#[deprecated(note = "renamed to `metamorphosis::eager`")]
+pub mod akt1 {
+    pub use crate::eager::*;
+}
+
+/// Former name of [`lazy`].
+#[deprecated(note = "renamed to `metamorphosis::lazy`")]
+pub mod akt2 {
+    pub use crate::lazy::*;
+}
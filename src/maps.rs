@@ -0,0 +1,562 @@
+use std::{
+    collections::{
+        hash_map::{Entry, OccupiedEntry},
+        HashMap, HashSet,
+    },
+    convert::Infallible,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+    mem::{self, size_of},
+    ptr,
+};
+
+/// Moves every entry of `from` into `into`. Keys only in `from` are moved
+/// as they are; for keys in both maps `combine` receives the value already in
+/// `into` first and is called exactly once.
+pub fn merge_maps<K, R, S1, S2>(
+    into: &mut HashMap<K, R, S1>,
+    from: HashMap<K, R, S2>,
+    mut combine: impl FnMut(&K, R, R) -> R,
+) where
+    K: Eq + Hash,
+    S1: BuildHasher,
+{
+    for (key, right) in from {
+        match into.entry(key) {
+            Entry::Occupied(entry) => {
+                update_entry(entry, |key, left| combine(key, left, right));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(right);
+            }
+        }
+    }
+}
+
+/// Merges all `maps` into the first one with [`merge_maps`], in iteration
+/// order.
+pub fn merge_all<K, R, M>(maps: M, mut combine: impl FnMut(&K, R, R) -> R) -> HashMap<K, R>
+where
+    K: Eq + Hash,
+    M: IntoIterator<Item = HashMap<K, R>>,
+{
+    let mut maps = maps.into_iter();
+    let mut merged = maps.next().unwrap_or_default();
+
+    for map in maps {
+        merge_maps(&mut merged, map, &mut combine);
+    }
+
+    merged
+}
+
+/// Replaces the value of `entry` with `update(key, value)` in place, so the
+/// key is not hashed again. Should `update` panic, the entry is removed.
+pub(crate) fn update_entry<K, R>(entry: OccupiedEntry<'_, K, R>, update: impl FnOnce(&K, R) -> R) {
+    let updated: Result<(), Infallible> =
+        try_update_entry(entry, |key, value| Ok(update(key, value)));
+
+    match updated {
+        Ok(()) => {}
+        Err(never) => match never {},
+    }
+}
+
+/// Fallible [`update_entry`]: should `update` fail or panic, the entry is
+/// removed, as its value was moved into `update`.
+pub(crate) fn try_update_entry<K, R, E>(
+    entry: OccupiedEntry<'_, K, R>,
+    update: impl FnOnce(&K, R) -> Result<R, E>,
+) -> Result<(), E> {
+    struct RemoveOnExit<'a, K, R>(Option<OccupiedEntry<'a, K, R>>);
+
+    impl<K, R> Drop for RemoveOnExit<'_, K, R> {
+        fn drop(&mut self) {
+            if let Some(entry) = self.0.take() {
+                // The value was moved out already; dropping it again would be
+                // a double free.
+                mem::forget(entry.remove_entry().1);
+            }
+        }
+    }
+
+    let mut guard = RemoveOnExit(Some(entry));
+    let entry = guard.0.as_mut().unwrap();
+    let slot: *mut R = entry.get_mut();
+    // SAFETY: `slot` points to an initialized value that is read out exactly
+    // once and written back before the guard is disarmed. If `update` fails
+    // or unwinds in between, the guard removes the entry without dropping
+    // its value.
+    let value = unsafe { ptr::read(slot) };
+    let value = update(entry.key(), value)?;
+
+    unsafe { ptr::write(slot, value) };
+    guard.0 = None;
+    Ok(())
+}
+
+/// Bytes a grouping result value owns on the heap, beyond its inline size.
+pub trait HeapSize {
+    fn heap_bytes(&self) -> usize;
+}
+
+impl HeapSize for usize {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl<T> HeapSize for Vec<T> {
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+/// Rough estimate of the memory held by a grouping result: one `(K, R)` slot
+/// plus a control byte per bucket, scaled by the table's 7/8 load factor,
+/// plus whatever the values own on the heap. Heap memory owned by keys is
+/// not counted.
+pub fn estimate_bytes<K, R, S>(result: &HashMap<K, R, S>) -> usize
+where
+    R: HeapSize,
+{
+    let slot = size_of::<K>() + size_of::<R>() + 1;
+    let table = result.capacity() * slot * 8 / 7;
+
+    table + result.values().map(HeapSize::heap_bytes).sum::<usize>()
+}
+
+/// Calls `f` with every group of a result map in ascending key order,
+/// borrowing keys and values instead of cloning them.
+pub fn for_each_group_sorted<K, R, S, F>(result: &HashMap<K, R, S>, mut f: F)
+where
+    K: Ord,
+    S: BuildHasher,
+    F: FnMut(&K, &R),
+{
+    let mut groups = result.iter().collect::<Vec<_>>();
+    groups.sort_unstable_by_key(|(key, _)| *key);
+
+    for (key, value) in groups {
+        f(key, value);
+    }
+}
+
+/// Per-key Jaccard similarity `|a ∩ b| / |a ∪ b|` of two groupings' member
+/// sets, for every key in either map. A key present in only one map scores
+/// `0.0`; a key whose member sets are both empty scores `1.0`.
+pub fn jaccard_by_group<K, V>(
+    a: &HashMap<K, HashSet<V>>,
+    b: &HashMap<K, HashSet<V>>,
+) -> HashMap<K, f64>
+where
+    K: Eq + Hash + Clone,
+    V: Eq + Hash,
+{
+    let mut similarity = HashMap::with_capacity(a.len().max(b.len()));
+
+    for (key, members) in a {
+        let score = match b.get(key) {
+            Some(other) if members.is_empty() && other.is_empty() => 1.0,
+            Some(other) => {
+                let shared = members.intersection(other).count();
+
+                shared as f64 / (members.len() + other.len() - shared) as f64
+            }
+            None => 0.0,
+        };
+
+        similarity.insert(key.clone(), score);
+    }
+
+    for key in b.keys() {
+        if !a.contains_key(key) {
+            similarity.insert(key.clone(), 0.0);
+        }
+    }
+
+    similarity
+}
+
+/// Flips a group map into its reverse index, e.g. tag → documents into
+/// document → tags. Values are moved; a key is cloned once for every value
+/// of its group beyond the first. A value listed twice under one key lists
+/// that key twice, see [`invert_groups_dedup`].
+pub fn invert_groups<K, V>(groups: HashMap<K, Vec<V>>) -> HashMap<V, Vec<K>>
+where
+    K: Clone,
+    V: Eq + Hash,
+{
+    invert_groups_with(groups, |_, _| true)
+}
+
+/// Like [`invert_groups`], listing every key at most once per value.
+pub fn invert_groups_dedup<K, V>(groups: HashMap<K, Vec<V>>) -> HashMap<V, Vec<K>>
+where
+    K: Clone + PartialEq,
+    V: Eq + Hash,
+{
+    // All values of one key are inverted in a row, so a repeated pair always
+    // finds its key at the end of the value's list.
+    invert_groups_with(groups, |keys, key| keys.last() != Some(key))
+}
+
+fn invert_groups_with<K, V>(
+    groups: HashMap<K, Vec<V>>,
+    mut keep: impl FnMut(&[K], &K) -> bool,
+) -> HashMap<V, Vec<K>>
+where
+    K: Clone,
+    V: Eq + Hash,
+{
+    let mut inverted: HashMap<V, Vec<K>> = HashMap::new();
+
+    for (key, values) in groups {
+        let mut values = values.into_iter().peekable();
+
+        while let Some(value) = values.next() {
+            let keys = inverted.entry(value).or_default();
+
+            if !keep(keys, &key) {
+                continue;
+            }
+
+            if values.peek().is_some() {
+                keys.push(key.clone());
+            } else {
+                keys.push(key);
+                break;
+            }
+        }
+    }
+
+    inverted
+}
+
+/// Keys that [`check_keys`] expected but did not find, and keys it found but
+/// did not expect. `missing` follows the order of the expected keys; `extra`
+/// follows the map's iteration order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeysMismatch<K> {
+    pub missing: Vec<K>,
+    pub extra: Vec<K>,
+}
+
+impl<K> fmt::Display for KeysMismatch<K>
+where
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "grouping keys differ: missing {:?}, extra {:?}",
+            self.missing, self.extra
+        )
+    }
+}
+
+impl<K> Error for KeysMismatch<K> where K: fmt::Debug {}
+
+/// Checks that the keys of `result` are exactly `expected`, ignoring order
+/// and duplicates in `expected`.
+pub fn check_keys<K, R, S>(result: &HashMap<K, R, S>, expected: &[K]) -> Result<(), KeysMismatch<K>>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    let expected_set = expected.iter().collect::<HashSet<_>>();
+    let mut missing = Vec::new();
+
+    for key in expected {
+        if !result.contains_key(key) && !missing.contains(key) {
+            missing.push(key.clone());
+        }
+    }
+
+    let extra = result
+        .keys()
+        .filter(|key| !expected_set.contains(key))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() && extra.is_empty() {
+        Ok(())
+    } else {
+        Err(KeysMismatch { missing, extra })
+    }
+}
+
+/// Panicking form of [`check_keys`] for tests.
+#[track_caller]
+pub fn assert_keys_eq<K, R, S>(result: &HashMap<K, R, S>, expected: &[K])
+where
+    K: Eq + Hash + Clone + fmt::Debug,
+    S: BuildHasher,
+{
+    if let Err(mismatch) = check_keys(result, expected) {
+        panic!("{}", mismatch);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        collections::{hash_map::Entry, HashMap, HashSet},
+        mem::size_of,
+        panic::{self, AssertUnwindSafe},
+    };
+
+    use super::{
+        assert_keys_eq, check_keys, estimate_bytes, for_each_group_sorted, invert_groups,
+        invert_groups_dedup, jaccard_by_group, merge_all, merge_maps, try_update_entry,
+        update_entry, KeysMismatch,
+    };
+    use crate::lazy::IntoGrouping;
+
+    fn sorted_pairs(
+        groups: &HashMap<&'static str, Vec<&'static str>>,
+    ) -> Vec<(&'static str, &'static str)> {
+        let mut pairs = Vec::new();
+
+        for (key, values) in groups {
+            for value in values {
+                pairs.push((*key, *value));
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn test_update_entry_drops_each_value_once() {
+        struct Tracked<'a>(&'a Cell<usize>);
+
+        impl Drop for Tracked<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut m = HashMap::from([("a", Tracked(&drops)), ("b", Tracked(&drops))]);
+
+        if let Entry::Occupied(entry) = m.entry("a") {
+            update_entry(entry, |_, old| {
+                drop(old);
+                Tracked(&drops)
+            });
+        }
+        assert_eq!(drops.get(), 1);
+
+        let unwound = panic::catch_unwind(AssertUnwindSafe(|| {
+            if let Entry::Occupied(entry) = m.entry("b") {
+                update_entry(entry, |_, _| panic!("operation failed"));
+            }
+        }));
+
+        assert!(unwound.is_err());
+        assert_eq!(drops.get(), 2);
+        assert!(!m.contains_key("b"));
+
+        m.insert("c", Tracked(&drops));
+
+        if let Entry::Occupied(entry) = m.entry("c") {
+            assert_eq!(try_update_entry(entry, |_, _| Err("failed")), Err("failed"));
+        }
+        assert_eq!(drops.get(), 3);
+        assert!(!m.contains_key("c"));
+
+        drop(m);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn test_invert_groups() {
+        let by_tag = HashMap::from([
+            ("rust", vec!["doc1", "doc2"]),
+            ("async", vec!["doc2", "doc2"]),
+        ]);
+        let mut by_doc = invert_groups(by_tag.clone());
+
+        by_doc.values_mut().for_each(|tags| tags.sort_unstable());
+        assert_eq!(
+            by_doc,
+            HashMap::from([
+                ("doc1", vec!["rust"]),
+                ("doc2", vec!["async", "async", "rust"])
+            ])
+        );
+
+        let mut deduped = invert_groups_dedup(by_tag.clone());
+
+        deduped.values_mut().for_each(|tags| tags.sort_unstable());
+        assert_eq!(deduped["doc2"], ["async", "rust"]);
+
+        let round_trip = invert_groups(invert_groups(by_tag.clone()));
+
+        assert_eq!(sorted_pairs(&round_trip), sorted_pairs(&by_tag));
+    }
+
+    #[test]
+    fn test_estimate_bytes() {
+        let groups = (0..1_000u64).grouping_by(|i| (i % 10) as u32).fold_with(
+            Vec::new,
+            |_, mut accumulator, item| {
+                accumulator.push(item);
+                accumulator
+            },
+        );
+        let contents = 1_000 * size_of::<u64>();
+        let estimate = estimate_bytes(&groups);
+
+        assert!(estimate > contents + 10 * (size_of::<u32>() + size_of::<Vec<u64>>()));
+        assert!(estimate < 3 * contents);
+
+        let counts = (0..1_000u64)
+            .grouping_by(|i| i % 10)
+            .with_expected_groups(10)
+            .each_count();
+        let estimate = estimate_bytes(&counts);
+
+        assert!(estimate >= 10 * 2 * size_of::<usize>());
+        assert!(estimate < 1_000);
+    }
+
+    #[test]
+    fn test_for_each_group_sorted() {
+        let groups = ["pear", "apple", "plum", "banana", "avocado", "cherry"]
+            .into_iter()
+            .grouping_by(|s| s.chars().next().unwrap().to_string())
+            .fold_with(Vec::new, |_, mut accumulator, item| {
+                accumulator.push(item);
+                accumulator
+            });
+        let mut visited = Vec::new();
+
+        for_each_group_sorted(&groups, |key, fruits| {
+            visited.push(format!("{}: {}", key, fruits.join(",")))
+        });
+
+        assert_eq!(
+            visited,
+            ["a: apple,avocado", "b: banana", "c: cherry", "p: pear,plum"]
+        );
+    }
+
+    #[test]
+    fn test_jaccard_by_group() {
+        let members = |groups: &[(&'static str, &[u32])]| {
+            groups
+                .iter()
+                .map(|(key, ids)| (*key, ids.iter().copied().collect::<HashSet<_>>()))
+                .collect::<HashMap<_, _>>()
+        };
+        let yesterday = members(&[
+            ("admins", &[1, 2, 3]),
+            ("guests", &[4, 5]),
+            ("bots", &[]),
+            ("legacy", &[9]),
+        ]);
+        let today = members(&[
+            ("admins", &[2, 3, 4]),
+            ("guests", &[6]),
+            ("bots", &[]),
+            ("new", &[7]),
+        ]);
+        let similarity = jaccard_by_group(&yesterday, &today);
+
+        assert_eq!(similarity.len(), 5);
+        assert_eq!(similarity["admins"], 0.5);
+        assert_eq!(similarity["guests"], 0.0);
+        assert_eq!(similarity["bots"], 1.0);
+        assert_eq!(similarity["legacy"], 0.0);
+        assert_eq!(similarity["new"], 0.0);
+    }
+
+    #[test]
+    fn test_check_keys() {
+        let counts = "one two three four five"
+            .split(' ')
+            .grouping_by(|s| s.len())
+            .each_count();
+
+        assert_eq!(check_keys(&counts, &[3, 4, 5]), Ok(()));
+        assert_eq!(check_keys(&counts, &[5, 3, 4, 3]), Ok(()));
+        assert_keys_eq(&counts, &[3, 4, 5]);
+
+        let mismatch = check_keys(&counts, &[3, 6, 7]).unwrap_err();
+        let mut extra = mismatch.extra.clone();
+        extra.sort_unstable();
+
+        assert_eq!(mismatch.missing, [6, 7]);
+        assert_eq!(extra, [4, 5]);
+        assert_eq!(
+            check_keys(&counts, &[3, 4, 5, 6]),
+            Err(KeysMismatch {
+                missing: vec![6],
+                extra: vec![]
+            })
+        );
+        assert_eq!(
+            check_keys(&counts, &[3, 4]).unwrap_err().to_string(),
+            "grouping keys differ: missing [], extra [5]"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing [6]")]
+    fn test_assert_keys_eq_panics() {
+        let counts = (0..10).grouping_by(|i| i % 3).each_count();
+
+        assert_keys_eq(&counts, &[0, 1, 2, 6]);
+    }
+
+    #[test]
+    fn test_merge_maps() {
+        let mut calls = 0;
+        let mut into = HashMap::from([("a", 1), ("b", 2)]);
+
+        merge_maps(&mut into, HashMap::from([("c", 3), ("d", 4)]), |_, l, r| {
+            calls += 1;
+            l + r
+        });
+
+        assert_eq!(
+            into,
+            HashMap::from([("a", 1), ("b", 2), ("c", 3), ("d", 4)])
+        );
+        assert_eq!(calls, 0);
+
+        merge_maps(
+            &mut into,
+            HashMap::from([("a", 10), ("b", 20), ("c", 30), ("d", 40)]),
+            |_, l, r| {
+                calls += 1;
+                l * 100 + r
+            },
+        );
+
+        assert_eq!(
+            into,
+            HashMap::from([("a", 110), ("b", 220), ("c", 330), ("d", 440)])
+        );
+        assert_eq!(calls, 4);
+    }
+
+    #[test]
+    fn test_merge_all() {
+        let words = "one two three four five six seven eight nine ten eleven twelve"
+            .split(' ')
+            .collect::<Vec<_>>();
+        let partials = words
+            .chunks(5)
+            .map(|chunk| chunk.iter().grouping_by(|w| w.len()).each_count());
+        let merged = merge_all(partials, |_, l, r| l + r);
+
+        assert_eq!(merged, words.iter().grouping_by(|w| w.len()).each_count());
+        assert!(merge_all(Vec::<HashMap<u8, u8>>::new(), |_, l, _| l).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
+use rayon::iter::ParallelIterator;
+
+use crate::maps;
+
+/// Grouping over a rayon [`ParallelIterator`]. Every worker folds into its own
+/// map and the partial maps are merged pairwise, so the order in which items
+/// reach an accumulator is unspecified: non-commutative operations must say
+/// how two partial accumulators of the same key combine.
+pub struct ParGrouping<P, Ks> {
+    iter: P,
+    key_selector: Ks,
+}
+
+impl<P, Ks, K> ParGrouping<P, Ks>
+where
+    P: ParallelIterator,
+    Ks: Fn(&P::Item) -> K + Sync + Send,
+    K: Eq + Hash + Send,
+{
+    /// Like the sequential `aggregate`, with `combine` merging two partial
+    /// accumulators of the same key built on different workers.
+    pub fn aggregate<R, O, C>(self, operation: O, combine: C) -> HashMap<K, R>
+    where
+        R: Send,
+        O: Fn(&K, Option<R>, P::Item) -> R + Sync + Send,
+        C: Fn(&K, R, R) -> R + Sync + Send,
+    {
+        let key_selector = &self.key_selector;
+
+        self.iter
+            .fold(HashMap::new, |mut m: HashMap<K, R>, item| {
+                match m.entry(key_selector(&item)) {
+                    Entry::Occupied(entry) => {
+                        maps::update_entry(entry, |key, accumulator| {
+                            operation(key, Some(accumulator), item)
+                        });
+                    }
+                    Entry::Vacant(entry) => {
+                        let value = operation(entry.key(), None, item);
+
+                        entry.insert(value);
+                    }
+                }
+
+                m
+            })
+            .reduce(HashMap::new, |mut merged, partial| {
+                maps::merge_maps(&mut merged, partial, &combine);
+                merged
+            })
+    }
+
+    pub fn fold<R, Init, O, C>(self, initial_value: Init, operation: O, combine: C) -> HashMap<K, R>
+    where
+        R: Send,
+        Init: Fn() -> R + Sync + Send,
+        O: Fn(R, P::Item) -> R + Sync + Send,
+        C: Fn(R, R) -> R + Sync + Send,
+    {
+        self.aggregate(
+            |_, accumulator, item| operation(accumulator.unwrap_or_else(&initial_value), item),
+            |_, left, right| combine(left, right),
+        )
+    }
+
+    /// Counts the items of each group, running the key selector on the rayon
+    /// pool too. For small sources the sequential `each_count` of
+    /// [`lazy::Grouping`](crate::lazy::Grouping) is usually faster, e.g.
+    /// `if len < threshold { iter.grouping_by(f).each_count() } else {
+    /// par_iter.par_grouping_by(f).each_count() }`.
+    pub fn each_count(self) -> HashMap<K, usize> {
+        self.fold(|| 0, |count, _| count + 1, |left, right| left + right)
+    }
+}
+
+pub trait IntoParallelGrouping: ParallelIterator {
+    fn par_grouping_by<Ks, K>(self, key_selector: Ks) -> ParGrouping<Self, Ks>
+    where
+        Ks: Fn(&Self::Item) -> K + Sync + Send,
+        K: Eq + Hash + Send;
+}
+
+impl<P> IntoParallelGrouping for P
+where
+    P: ParallelIterator,
+{
+    fn par_grouping_by<Ks, K>(self, key_selector: Ks) -> ParGrouping<Self, Ks>
+    where
+        Ks: Fn(&Self::Item) -> K + Sync + Send,
+        K: Eq + Hash + Send,
+    {
+        ParGrouping {
+            iter: self,
+            key_selector,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+
+    use super::IntoParallelGrouping;
+    use crate::{fixtures::random_data, lazy::IntoGrouping};
+
+    #[test]
+    fn test_each_count_matches_sequential() {
+        for seed in 1..=16 {
+            let data = random_data(seed, 5_000);
+            let parallel = data.par_iter().par_grouping_by(|x| *x % 37).each_count();
+            let sequential = data.iter().grouping_by(|x| *x % 37).each_count();
+
+            assert_eq!(parallel, sequential);
+        }
+    }
+
+    #[test]
+    fn test_fold_and_aggregate() {
+        let data = random_data(7, 2_000);
+        let sums = data.par_iter().par_grouping_by(|x| *x % 5).fold(
+            || 0u128,
+            |sum, x| sum + *x as u128,
+            |a, b| a + b,
+        );
+        let sequential = data
+            .iter()
+            .grouping_by(|x| *x % 5)
+            .fold(0u128, |sum, x| sum + *x as u128);
+
+        assert_eq!(sums, sequential);
+
+        let mut members = (0..1_000u32)
+            .into_par_iter()
+            .par_grouping_by(|i| i % 3)
+            .aggregate(
+                |_, accumulator: Option<Vec<u32>>, i| {
+                    let mut accumulator = accumulator.unwrap_or_default();
+                    accumulator.push(i);
+                    accumulator
+                },
+                |_, mut left, right| {
+                    left.extend(right);
+                    left
+                },
+            );
+
+        for group in members.values_mut() {
+            group.sort_unstable();
+        }
+
+        assert_eq!(members[&1], (1..1_000).step_by(3).collect::<Vec<_>>());
+    }
+}
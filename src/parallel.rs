@@ -0,0 +1,635 @@
+use std::{
+    collections::{
+        hash_map::{Entry, RandomState},
+        HashMap,
+    },
+    hash::{BuildHasher, Hash, Hasher},
+    sync::{
+        mpsc::{IntoIter, Receiver, RecvTimeoutError},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "dashmap")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+use crate::{
+    lazy::{Grouping, IntoGrouping},
+    maps::{self, merge_all},
+};
+
+/// Splits `data` into up to `threads` contiguous chunks, aggregates each one
+/// on its own scoped thread and merges the partial maps in chunk order:
+/// `merge` always receives the accumulator of the earlier chunk first.
+/// `threads == 0` is treated as `1`.
+pub fn sharded_aggregate<T, K, R, Ks, O, M>(
+    data: &[T],
+    key_selector: Ks,
+    threads: usize,
+    operation: O,
+    merge: M,
+) -> HashMap<K, R>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    R: Send,
+    Ks: Fn(&T) -> K + Sync,
+    O: Fn(&K, Option<R>, &T) -> R + Sync,
+    M: Fn(&K, R, R) -> R,
+{
+    let chunk_size = ((data.len() + threads.max(1) - 1) / threads.max(1)).max(1);
+    let partials = thread::scope(|scope| {
+        let workers = data
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(|| {
+                    chunk
+                        .iter()
+                        .grouping_by(|item| key_selector(item))
+                        .aggregate(|key, accumulator, item| operation(key, accumulator, item))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    merge_all(partials, merge)
+}
+
+pub fn sharded_each_count<T, K, Ks>(
+    data: &[T],
+    key_selector: Ks,
+    threads: usize,
+) -> HashMap<K, usize>
+where
+    T: Sync,
+    K: Eq + Hash + Send,
+    Ks: Fn(&T) -> K + Sync,
+{
+    sharded_aggregate(
+        data,
+        key_selector,
+        threads,
+        |_, count, _| count.unwrap_or(0) + 1,
+        |_, left, right| left + right,
+    )
+}
+
+const SHARDS: usize = 16;
+
+type SharedOperation<T, K, R> = Box<dyn Fn(&K, Option<R>, T) -> R + Send + Sync>;
+
+/// A grouped accumulator that many threads can feed at once. Keys are spread
+/// over a fixed number of independently locked shards, and `operation` has
+/// the same shape as in `aggregate`.
+///
+/// A panic inside `operation` poisons its shard; later calls touching that
+/// shard panic as well.
+pub struct GroupAccumulator<T, K, R> {
+    shards: Vec<Mutex<HashMap<K, R>>>,
+    hasher: RandomState,
+    key_selector: Box<dyn Fn(&T) -> K + Send + Sync>,
+    operation: SharedOperation<T, K, R>,
+}
+
+impl<T, K, R> GroupAccumulator<T, K, R>
+where
+    K: Eq + Hash,
+{
+    pub fn new(
+        key_selector: impl Fn(&T) -> K + Send + Sync + 'static,
+        operation: impl Fn(&K, Option<R>, T) -> R + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            hasher: RandomState::new(),
+            key_selector: Box::new(key_selector),
+            operation: Box::new(operation),
+        }
+    }
+
+    /// Picks up from a [`into_inner`](Self::into_inner) or
+    /// [`snapshot`](Self::snapshot) result, e.g. one checkpointed with
+    /// `serde`, with the key selector and operation supplied anew.
+    pub fn resume(
+        state: HashMap<K, R>,
+        key_selector: impl Fn(&T) -> K + Send + Sync + 'static,
+        operation: impl Fn(&K, Option<R>, T) -> R + Send + Sync + 'static,
+    ) -> Self {
+        let mut accumulator = Self::new(key_selector, operation);
+
+        for (key, value) in state {
+            let shard = accumulator.shard_of(&key);
+
+            accumulator.shards[shard]
+                .get_mut()
+                .unwrap()
+                .insert(key, value);
+        }
+
+        accumulator
+    }
+
+    pub fn feed(&self, item: T) {
+        let key = (self.key_selector)(&item);
+        let mut shard = self.shards[self.shard_of(&key)].lock().unwrap();
+
+        match shard.entry(key) {
+            Entry::Occupied(entry) => {
+                maps::update_entry(entry, |key, accumulator| {
+                    (self.operation)(key, Some(accumulator), item)
+                });
+            }
+            Entry::Vacant(entry) => {
+                let value = (self.operation)(entry.key(), None, item);
+
+                entry.insert(value);
+            }
+        }
+    }
+
+    /// Copies the current accumulators. Shards are locked one after another,
+    /// so every value is one the accumulator actually held, but values of
+    /// different keys may come from different moments.
+    pub fn snapshot(&self) -> HashMap<K, R>
+    where
+        K: Clone,
+        R: Clone,
+    {
+        let mut snapshot = HashMap::new();
+
+        for shard in &self.shards {
+            let shard = shard.lock().unwrap();
+
+            snapshot.extend(
+                shard
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+        }
+
+        snapshot
+    }
+
+    pub fn into_inner(self) -> HashMap<K, R> {
+        self.shards
+            .into_iter()
+            .flat_map(|shard| shard.into_inner().unwrap())
+            .collect()
+    }
+
+    fn shard_of(&self, key: &K) -> usize {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+
+        hasher.finish() as usize % self.shards.len()
+    }
+}
+
+/// Serializes the accumulators as a map, e.g. to checkpoint them for
+/// [`GroupAccumulator::resume`]. All shards stay locked meanwhile, so the
+/// checkpoint is consistent across keys.
+#[cfg(feature = "serde")]
+impl<T, K, R> Serialize for GroupAccumulator<T, K, R>
+where
+    K: Serialize,
+    R: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let shards = self
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap())
+            .collect::<Vec<_>>();
+
+        serializer.collect_map(shards.iter().flat_map(|shard| shard.iter()))
+    }
+}
+
+/// Groups whatever arrives on `receiver`, e.g. on the one thread that owns
+/// the receiving end of the workers' channel.
+pub fn group_from_receiver<T, K, Ks>(
+    receiver: Receiver<T>,
+    key_selector: Ks,
+) -> ReceiverGrouping<T, Ks, K>
+where
+    Ks: FnMut(&T) -> K,
+{
+    ReceiverGrouping {
+        receiver,
+        key_selector,
+        disconnected: false,
+    }
+}
+
+pub struct ReceiverGrouping<T, Ks, K>
+where
+    Ks: FnMut(&T) -> K,
+{
+    receiver: Receiver<T>,
+    key_selector: Ks,
+    disconnected: bool,
+}
+
+impl<T, Ks, K> ReceiverGrouping<T, Ks, K>
+where
+    Ks: FnMut(&T) -> K,
+    K: Eq + Hash,
+{
+    /// Lazy grouping over the channel, whose terminals block until every
+    /// sender is dropped.
+    pub fn into_grouping(self) -> Grouping<IntoIter<T>, Ks, K> {
+        self.receiver.into_iter().grouping_by(self.key_selector)
+    }
+
+    /// Aggregates the items received until `deadline`, leaving the channel
+    /// open for the next call. Returns early with what was accumulated once
+    /// every sender is dropped, see
+    /// [`is_disconnected`](Self::is_disconnected).
+    pub fn aggregate_until<R, O>(&mut self, deadline: Instant, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, T) -> R,
+    {
+        let mut m = HashMap::new();
+
+        while !self.disconnected {
+            let now = Instant::now();
+
+            if now >= deadline {
+                break;
+            }
+
+            let item = match self.receiver.recv_timeout(deadline - now) {
+                Ok(item) => item,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            };
+
+            match m.entry((self.key_selector)(&item)) {
+                Entry::Occupied(entry) => {
+                    maps::update_entry(entry, |key, accumulator| {
+                        operation(key, Some(accumulator), item)
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    let value = operation(entry.key(), None, item);
+
+                    entry.insert(value);
+                }
+            }
+        }
+
+        m
+    }
+
+    /// Like [`aggregate_until`](Self::aggregate_until), `duration` from now.
+    pub fn aggregate_for<R, O>(&mut self, duration: Duration, operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, T) -> R,
+    {
+        self.aggregate_until(Instant::now() + duration, operation)
+    }
+
+    /// Whether a previous call found every sender dropped, so later calls
+    /// return empty maps right away.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+}
+
+/// Counter that many threads can increment without serializing on a lock
+/// for keys that already exist: each count is an atomic inside a `DashMap`.
+#[cfg(feature = "dashmap")]
+pub struct ConcurrentCounter<K>
+where
+    K: Eq + Hash,
+{
+    counts: dashmap::DashMap<K, AtomicU64>,
+}
+
+#[cfg(feature = "dashmap")]
+impl<K> ConcurrentCounter<K>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            counts: dashmap::DashMap::new(),
+        }
+    }
+
+    pub fn increment(&self, key: K) {
+        if let Some(count) = self.counts.get(&key) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        self.counts
+            .entry(key)
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, key: &K) -> u64 {
+        self.counts
+            .get(key)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    pub fn into_hash_map(self) -> HashMap<K, u64> {
+        self.counts
+            .into_iter()
+            .map(|(key, count)| (key, count.into_inner()))
+            .collect()
+    }
+}
+
+#[cfg(feature = "dashmap")]
+impl<K> Default for ConcurrentCounter<K>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts `data` on up to `threads` scoped threads that all increment one
+/// shared [`ConcurrentCounter`].
+#[cfg(feature = "dashmap")]
+pub fn each_count_concurrent<T, K, Ks>(
+    data: &[T],
+    key_selector: Ks,
+    threads: usize,
+) -> HashMap<K, u64>
+where
+    T: Sync,
+    K: Eq + Hash + Send + Sync,
+    Ks: Fn(&T) -> K + Sync,
+{
+    let counter = ConcurrentCounter::new();
+    let chunk_size = ((data.len() + threads.max(1) - 1) / threads.max(1)).max(1);
+
+    thread::scope(|scope| {
+        for chunk in data.chunks(chunk_size) {
+            let counter = &counter;
+            let key_selector = &key_selector;
+
+            scope.spawn(move || {
+                for item in chunk {
+                    counter.increment(key_selector(item));
+                }
+            });
+        }
+    });
+
+    counter.into_hash_map()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::{group_from_receiver, sharded_aggregate, sharded_each_count, GroupAccumulator};
+    use crate::{fixtures::random_data, lazy::IntoGrouping};
+
+    #[test]
+    fn test_sharded_each_count() {
+        for (seed, len) in [(1, 10_000), (2, 3), (3, 0)] {
+            let data = random_data(seed, len);
+            let sequential = data.iter().grouping_by(|x| *x % 13).each_count();
+
+            for threads in [0, 1, 2, 8] {
+                assert_eq!(sharded_each_count(&data, |x| x % 13, threads), sequential);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sharded_aggregate_merges_in_chunk_order() {
+        let data = (0..100u32).collect::<Vec<_>>();
+        let groups = sharded_aggregate(
+            &data,
+            |i| i % 2,
+            8,
+            |_, accumulator: Option<Vec<u32>>, i| {
+                let mut accumulator = accumulator.unwrap_or_default();
+                accumulator.push(*i);
+                accumulator
+            },
+            |_, mut left, right| {
+                left.extend(right);
+                left
+            },
+        );
+
+        assert_eq!(groups[&0], (0..100).step_by(2).collect::<Vec<_>>());
+        assert_eq!(groups[&1], (1..100).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_group_from_receiver() {
+        let (sender, receiver) = mpsc::channel();
+        let producers = (0..4u64)
+            .map(|worker| {
+                let sender = sender.clone();
+
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        sender.send(worker * 1_000 + i % 5).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        drop(sender);
+
+        let counts = group_from_receiver(receiver, |event: &u64| event % 5)
+            .into_grouping()
+            .each_count();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(counts, (0..5).map(|key| (key, 200)).collect());
+    }
+
+    #[test]
+    fn test_receiver_aggregate_for() {
+        let (sender, receiver) = mpsc::channel();
+        let mut grouping = group_from_receiver(receiver, |event: &&str| *event);
+        let count = |_: &&str, count: Option<usize>, _| count.unwrap_or(0) + 1;
+
+        sender.send("click").unwrap();
+        sender.send("view").unwrap();
+        sender.send("click").unwrap();
+
+        let window = grouping.aggregate_for(Duration::from_millis(20), count);
+
+        assert_eq!(window, [("click", 2), ("view", 1)].into());
+        assert!(!grouping.is_disconnected());
+
+        sender.send("view").unwrap();
+        drop(sender);
+
+        let rest = grouping.aggregate_for(Duration::from_secs(60), count);
+
+        assert_eq!(rest, [("view", 1)].into());
+        assert!(grouping.is_disconnected());
+    }
+
+    #[test]
+    fn test_group_accumulator() {
+        let data = random_data(11, 8_000);
+        let accumulator = GroupAccumulator::new(
+            |x: &u64| x % 10,
+            |_, totals: Option<(usize, u64)>, x| {
+                let (count, sum) = totals.unwrap_or_default();
+
+                (count + 1, sum.wrapping_add(x))
+            },
+        );
+
+        thread::scope(|scope| {
+            for chunk in data.chunks(1_000) {
+                let accumulator = &accumulator;
+
+                scope.spawn(move || {
+                    for x in chunk {
+                        accumulator.feed(*x);
+                    }
+                });
+            }
+        });
+
+        let sequential = data
+            .iter()
+            .grouping_by(|x| *x % 10)
+            .aggregate(|_, totals, x| {
+                let (count, sum): (usize, u64) = totals.unwrap_or_default();
+
+                (count + 1, sum.wrapping_add(*x))
+            });
+
+        assert_eq!(accumulator.snapshot(), sequential);
+        assert_eq!(accumulator.into_inner(), sequential);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_group_accumulator_checkpoint() {
+        use std::collections::HashMap;
+
+        let count = |_: &u64, count: Option<usize>, _| count.unwrap_or(0) + 1;
+        let data = random_data(5, 2_000);
+        let (before, after) = data.split_at(1_200);
+        let accumulator = GroupAccumulator::new(|x: &u64| x % 6, count);
+
+        for x in before {
+            accumulator.feed(*x);
+        }
+
+        let checkpoint = serde_json::to_string(&accumulator).unwrap();
+        let state: HashMap<u64, usize> = serde_json::from_str(&checkpoint).unwrap();
+        let resumed = GroupAccumulator::resume(state, |x: &u64| x % 6, count);
+
+        for x in after {
+            resumed.feed(*x);
+        }
+
+        assert_eq!(
+            resumed.into_inner(),
+            data.iter().grouping_by(|x| *x % 6).each_count()
+        );
+    }
+
+    #[test]
+    fn test_group_accumulator_snapshot_is_consistent() {
+        let accumulator = GroupAccumulator::new(
+            |i: &u32| i % 4,
+            |_, pair: Option<(u32, u32)>, _| {
+                let (left, right) = pair.unwrap_or_default();
+
+                (left + 1, right + 1)
+            },
+        );
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..2_000 {
+                        accumulator.feed(i);
+                    }
+                });
+            }
+
+            for _ in 0..50 {
+                for (left, right) in accumulator.snapshot().into_values() {
+                    assert_eq!(left, right);
+                }
+            }
+        });
+
+        let totals = accumulator.into_inner();
+
+        assert_eq!(totals.len(), 4);
+        assert!(totals.values().all(|pair| *pair == (2_000, 2_000)));
+    }
+
+    #[cfg(feature = "dashmap")]
+    #[test]
+    fn test_concurrent_counter() {
+        use super::{each_count_concurrent, ConcurrentCounter};
+
+        let counter = ConcurrentCounter::new();
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for i in 0..10_000u32 {
+                        counter.increment(i % 3);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(counter.get(&0), 26_672);
+        assert_eq!(counter.get(&7), 0);
+
+        let totals = counter.into_hash_map();
+
+        assert_eq!(totals.len(), 3);
+        assert_eq!(totals.values().sum::<u64>(), 80_000);
+        assert_eq!(totals[&1], 26_664);
+
+        let data = random_data(5, 3_000);
+        let sequential = data.iter().grouping_by(|x| *x % 9).each_count();
+
+        for threads in [1, 8] {
+            let concurrent = each_count_concurrent(&data, |x| x % 9, threads);
+
+            assert_eq!(
+                concurrent
+                    .into_iter()
+                    .map(|(key, count)| (key, count as usize))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                sequential
+            );
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Everything a typical pipeline needs, in one import.
+//!
+//! The lazy, iterator-based API is the default; the eager traits are renamed
+//! so both can be used side by side:
+//!
+//! ```
+//! use metamorphosis::prelude::*;
+//!
+//! let eager = vec!["ant", "bee", "wasp"].grouping_by(|s| s.len()).each_count();
+//! let lazy = ["ant", "bee", "wasp"]
+//!     .into_iter()
+//!     .grouping_by(|s| s.len())
+//!     .each_count();
+//!
+//! assert_eq!(eager, lazy);
+//! ```
+
+pub use crate::{
+    eager::{Grouping as EagerGrouping, IntoGrouping as IntoEagerGrouping},
+    grouped::{GroupedMap, HasLen, IntoGroupedMap},
+    intern::{KeyInterner, Symbol},
+    lazy::{GroupRanges, Grouping, IntoGrouping, KeyLimitExceeded, OverflowPolicy},
+    maps::KeysMismatch,
+    registry::{KeySelectorRegistry, UnknownKeySelector},
+};
+
+#[cfg(feature = "serde")]
+pub use crate::json::IntoPrettyJson;
+#[cfg(feature = "rayon")]
+pub use crate::par::IntoParallelGrouping;
+#[cfg(feature = "async")]
+pub use crate::stream::IntoGroupingStream;
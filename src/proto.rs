@@ -0,0 +1,28 @@
+//! Hand-written `prost` messages matching `proto/group_counts.proto`, so no
+//! `protoc` is needed at build time.
+
+use std::collections::HashMap;
+
+/// Per-group item counts, keyed by the `Display` output of the group key.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GroupCounts {
+    #[prost(message, repeated, tag = "1")]
+    pub counts: Vec<GroupCount>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GroupCount {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(uint64, tag = "2")]
+    pub value: u64,
+}
+
+impl GroupCounts {
+    pub fn into_hash_map(self) -> HashMap<String, u64> {
+        self.counts
+            .into_iter()
+            .map(|count| (count.key, count.value))
+            .collect()
+    }
+}
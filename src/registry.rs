@@ -0,0 +1,133 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+/// Key selector as lent out by a [`KeySelectorRegistry`].
+pub type RegisteredKeySelector<'r, T, K> = &'r dyn Fn(&T) -> K;
+
+type BoxedKeySelector<T, K> = Box<dyn Fn(&T) -> K>;
+
+/// Named key selectors, so the grouping dimension can be picked at runtime,
+/// e.g. from a config value such as `"by_country"`.
+pub struct KeySelectorRegistry<T, K> {
+    selectors: HashMap<String, BoxedKeySelector<T, K>>,
+}
+
+impl<T, K> KeySelectorRegistry<T, K> {
+    pub fn new() -> Self {
+        Self {
+            selectors: HashMap::new(),
+        }
+    }
+
+    /// Registers `selector` under `name`, replacing any selector registered
+    /// under the same name before.
+    pub fn register<F>(&mut self, name: impl Into<String>, selector: F) -> &mut Self
+    where
+        F: Fn(&T) -> K + 'static,
+    {
+        self.selectors.insert(name.into(), Box::new(selector));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Result<RegisteredKeySelector<'_, T, K>, UnknownKeySelector> {
+        self.selectors
+            .get(name)
+            .map(|selector| selector.as_ref())
+            .ok_or_else(|| UnknownKeySelector {
+                name: name.to_owned(),
+            })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.selectors.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.selectors.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.selectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+}
+
+impl<T, K> Default for KeySelectorRegistry<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, K> fmt::Debug for KeySelectorRegistry<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownKeySelector {
+    pub name: String,
+}
+
+impl fmt::Display for UnknownKeySelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no key selector registered as {:?}", self.name)
+    }
+}
+
+impl Error for UnknownKeySelector {}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{KeySelectorRegistry, UnknownKeySelector};
+    use crate::lazy::IntoGrouping;
+
+    struct Customer {
+        country: &'static str,
+        plan: &'static str,
+    }
+
+    #[test]
+    fn test_grouping_by_name() {
+        let customers = [
+            Customer {
+                country: "TW",
+                plan: "free",
+            },
+            Customer {
+                country: "JP",
+                plan: "pro",
+            },
+            Customer {
+                country: "TW",
+                plan: "pro",
+            },
+        ];
+        let mut registry = KeySelectorRegistry::new();
+
+        registry
+            .register("by_country", |c: &&Customer| c.country)
+            .register("by_plan", |c: &&Customer| c.plan);
+
+        let by_plan = customers
+            .iter()
+            .grouping_by_name("by_plan", &registry)
+            .unwrap()
+            .each_count();
+
+        assert_eq!(by_plan, HashMap::from([("free", 1), ("pro", 2)]));
+        assert_eq!(
+            customers
+                .iter()
+                .grouping_by_name("by_region", &registry)
+                .err(),
+            Some(UnknownKeySelector {
+                name: "by_region".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,764 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    convert::Infallible,
+    future::{ready, Future},
+    hash::Hash,
+    pin::pin,
+};
+#[cfg(feature = "tokio")]
+use std::{
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use futures_util::{stream, FutureExt, StreamExt};
+#[cfg(feature = "tokio")]
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
+
+/// Async counterpart of [`lazy::Grouping`](crate::lazy::Grouping): the
+/// terminals drive the stream to completion and resolve to the same maps the
+/// sync terminals return for the same items.
+pub struct GroupingStream<S, Ks, K>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> K,
+{
+    stream: S,
+    key_selector: Ks,
+}
+
+impl<S, Ks, K> GroupingStream<S, Ks, K>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> K,
+    K: Eq + Hash,
+{
+    pub async fn aggregate<R, O>(self, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, S::Item) -> R,
+    {
+        self.aggregate_async(|key, accumulator, item| ready(operation(key, accumulator, item)))
+            .await
+    }
+
+    /// Like [`aggregate`](Self::aggregate), awaiting `operation` for one item
+    /// at a time in stream order.
+    pub async fn aggregate_async<R, O, F>(self, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(&K, Option<R>, S::Item) -> F,
+        F: Future<Output = R>,
+    {
+        self.try_aggregate_async(|key, accumulator, item| operation(key, accumulator, item).map(Ok))
+            .await
+            .unwrap_or_else(|never: Infallible| match never {})
+    }
+
+    /// Like [`aggregate_async`](Self::aggregate_async), stopping at the first
+    /// error `operation` resolves to.
+    pub async fn try_aggregate_async<R, E, O, F>(self, operation: O) -> Result<HashMap<K, R>, E>
+    where
+        O: FnMut(&K, Option<R>, S::Item) -> F,
+        F: Future<Output = Result<R, E>>,
+    {
+        let mut key_selector = self.key_selector;
+
+        try_aggregate_keyed(
+            self.stream.map(move |item| (key_selector(&item), item)),
+            operation,
+        )
+        .await
+    }
+
+    /// Async counterpart of
+    /// [`lazy::Grouping::scan_aggregate`](crate::lazy::Grouping::scan_aggregate):
+    /// yields every group's new accumulator as soon as an item arrives.
+    pub fn aggregate_updates<R, O>(self, mut operation: O) -> impl Stream<Item = (K, R)>
+    where
+        O: FnMut(&K, Option<R>, S::Item) -> R,
+        K: Clone,
+        R: Clone,
+    {
+        let mut key_selector = self.key_selector;
+        let mut accumulators: HashMap<K, Option<R>> = HashMap::new();
+
+        self.stream.map(move |item| {
+            let key = key_selector(&item);
+            let accumulator = accumulators.entry(key.clone()).or_insert(None);
+            let value = operation(&key, accumulator.take(), item);
+
+            *accumulator = Some(value.clone());
+            (key, value)
+        })
+    }
+
+    /// Async counterpart of
+    /// [`IntoGrouping::streaming_groups_sorted`](crate::lazy::IntoGrouping::streaming_groups_sorted):
+    /// yields each run of equal consecutive keys as soon as the key changes
+    /// or the stream ends, holding only the current run. A key that reappears
+    /// after a different one starts a new run.
+    pub fn consecutive_groups(self) -> impl Stream<Item = (K, Vec<S::Item>)>
+    where
+        K: PartialEq,
+    {
+        self.consecutive_fold_with(Vec::new, |mut items, item| {
+            items.push(item);
+            items
+        })
+    }
+
+    /// Like [`consecutive_groups`](Self::consecutive_groups), folding each
+    /// run from `initial_value` instead of collecting it.
+    pub fn consecutive_fold<R, O>(
+        self,
+        initial_value: R,
+        operation: O,
+    ) -> impl Stream<Item = (K, R)>
+    where
+        K: PartialEq,
+        R: Clone,
+        O: FnMut(R, S::Item) -> R,
+    {
+        self.consecutive_fold_with(move || initial_value.clone(), operation)
+    }
+
+    fn consecutive_fold_with<R, Ivg, O>(
+        self,
+        mut initial_value_provider: Ivg,
+        operation: O,
+    ) -> impl Stream<Item = (K, R)>
+    where
+        K: PartialEq,
+        Ivg: FnMut() -> R,
+        O: FnMut(R, S::Item) -> R,
+    {
+        let mut key_selector = self.key_selector;
+        let keyed = Box::pin(
+            self.stream
+                .map(move |item| (key_selector(&item), item))
+                .fuse(),
+        );
+
+        stream::unfold(
+            (keyed, None, operation),
+            move |(mut keyed, pending, mut operation)| {
+                let initial_value = initial_value_provider();
+
+                async move {
+                    let (key, first) = match pending {
+                        Some(pending) => pending,
+                        None => keyed.next().await?,
+                    };
+                    let mut accumulator = operation(initial_value, first);
+                    let mut pending = None;
+
+                    while let Some((next_key, item)) = keyed.next().await {
+                        if next_key != key {
+                            pending = Some((next_key, item));
+                            break;
+                        }
+
+                        accumulator = operation(accumulator, item);
+                    }
+
+                    Some(((key, accumulator), (keyed, pending, operation)))
+                }
+            },
+        )
+    }
+
+    pub async fn fold_with_key<R, Ivs, O>(
+        self,
+        mut initial_value_selector: Ivs,
+        mut operation: O,
+    ) -> HashMap<K, R>
+    where
+        Ivs: FnMut(&K, &S::Item) -> R,
+        O: FnMut(&K, R, S::Item) -> R,
+    {
+        self.aggregate(|key, accumulator, item| {
+            operation(
+                key,
+                accumulator.unwrap_or(initial_value_selector(key, &item)),
+                item,
+            )
+        })
+        .await
+    }
+
+    pub async fn fold<R, O>(self, initial_value: R, mut operation: O) -> HashMap<K, R>
+    where
+        O: FnMut(R, S::Item) -> R,
+        R: Clone,
+    {
+        self.aggregate(|_, accumulator, item| {
+            operation(accumulator.unwrap_or(initial_value.clone()), item)
+        })
+        .await
+    }
+
+    pub async fn each_count(self) -> HashMap<K, usize> {
+        let mut key_selector = self.key_selector;
+        let mut stream = pin!(self.stream);
+        let mut counts = HashMap::new();
+
+        while let Some(item) = stream.next().await {
+            *counts.entry(key_selector(&item)).or_insert(0) += 1;
+        }
+
+        counts
+    }
+}
+
+/// [`GroupingStream`] whose key selector has to be awaited, e.g. because it
+/// looks the key up in a remote service.
+pub struct AsyncGroupingStream<S, Ks, F>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> F,
+    F: Future,
+{
+    stream: S,
+    key_selector: Ks,
+    concurrency: usize,
+}
+
+impl<S, Ks, F> AsyncGroupingStream<S, Ks, F>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> F,
+    F: Future,
+    F::Output: Eq + Hash,
+{
+    /// Runs up to `n` key selector futures at once. Items still reach the
+    /// terminals in stream order. `n == 0` is treated as `1`, the default.
+    pub fn buffered(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    pub async fn aggregate<R, O>(self, mut operation: O) -> HashMap<F::Output, R>
+    where
+        O: FnMut(&F::Output, Option<R>, S::Item) -> R,
+    {
+        self.aggregate_async(|key, accumulator, item| ready(operation(key, accumulator, item)))
+            .await
+    }
+
+    pub async fn aggregate_async<R, O, G>(self, mut operation: O) -> HashMap<F::Output, R>
+    where
+        O: FnMut(&F::Output, Option<R>, S::Item) -> G,
+        G: Future<Output = R>,
+    {
+        self.try_aggregate_async(|key, accumulator, item| operation(key, accumulator, item).map(Ok))
+            .await
+            .unwrap_or_else(|never: Infallible| match never {})
+    }
+
+    pub async fn try_aggregate_async<R, E, O, G>(
+        self,
+        operation: O,
+    ) -> Result<HashMap<F::Output, R>, E>
+    where
+        O: FnMut(&F::Output, Option<R>, S::Item) -> G,
+        G: Future<Output = Result<R, E>>,
+    {
+        let mut key_selector = self.key_selector;
+        let keyed = self
+            .stream
+            .map(move |item| {
+                let key = key_selector(&item);
+
+                async move { (key.await, item) }
+            })
+            .buffered(self.concurrency);
+
+        try_aggregate_keyed(keyed, operation).await
+    }
+
+    pub async fn each_count(self) -> HashMap<F::Output, usize> {
+        self.aggregate(|_, count, _| count.unwrap_or(0) + 1).await
+    }
+}
+
+async fn try_aggregate_keyed<P, T, K, R, E, O, F>(
+    keyed: P,
+    mut operation: O,
+) -> Result<HashMap<K, R>, E>
+where
+    P: Stream<Item = (K, T)>,
+    K: Eq + Hash,
+    O: FnMut(&K, Option<R>, T) -> F,
+    F: Future<Output = Result<R, E>>,
+{
+    let mut keyed = pin!(keyed);
+    let mut m: HashMap<K, Option<R>> = HashMap::new();
+
+    while let Some((key, item)) = keyed.next().await {
+        match m.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let accumulator = entry.get_mut().take();
+                let value = operation(entry.key(), accumulator, item).await?;
+
+                *entry.get_mut() = Some(value);
+            }
+            Entry::Vacant(entry) => {
+                let value = operation(entry.key(), None, item).await?;
+
+                entry.insert(Some(value));
+            }
+        }
+    }
+
+    Ok(m.into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+        .collect())
+}
+
+pub trait IntoGroupingStream<S>
+where
+    S: Stream,
+{
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> GroupingStream<S, Ks, K>
+    where
+        Ks: FnMut(&S::Item) -> K;
+
+    /// Groups by a key selector that returns a future, awaited per item.
+    fn grouping_by_async<Ks, F>(self, key_selector: Ks) -> AsyncGroupingStream<S, Ks, F>
+    where
+        Ks: FnMut(&S::Item) -> F,
+        F: Future;
+}
+
+impl<S> IntoGroupingStream<S> for S
+where
+    S: Stream,
+{
+    fn grouping_by<Ks, K>(self, key_selector: Ks) -> GroupingStream<S, Ks, K>
+    where
+        Ks: FnMut(&S::Item) -> K,
+    {
+        GroupingStream {
+            stream: self,
+            key_selector,
+        }
+    }
+
+    fn grouping_by_async<Ks, F>(self, key_selector: Ks) -> AsyncGroupingStream<S, Ks, F>
+    where
+        Ks: FnMut(&S::Item) -> F,
+        F: Future,
+    {
+        AsyncGroupingStream {
+            stream: self,
+            key_selector,
+            concurrency: 1,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, Ks, K> GroupingStream<S, Ks, K>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> K,
+{
+    /// Yields the per-key counts of every `window`, starting one `window`
+    /// after the call. Items count towards the window in which they were
+    /// received, windows without items still yield an (empty) map, and
+    /// counts reset between windows unless [`Windowed::cumulative`] is set.
+    /// When the source ends, a last partial window is yielded if it received
+    /// any items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero, or if not called within a Tokio runtime.
+    pub fn windowed(self, window: Duration) -> Windowed<S, Ks, K> {
+        let mut interval = time::interval_at(Instant::now() + window, window);
+
+        interval.set_missed_tick_behavior(MissedTickBehavior::Burst);
+        Windowed {
+            stream: Box::pin(self.stream),
+            key_selector: self.key_selector,
+            interval,
+            counts: HashMap::new(),
+            cumulative: false,
+            received: false,
+            done: false,
+        }
+    }
+}
+
+/// Stream of per-window counts, see [`GroupingStream::windowed`].
+#[cfg(feature = "tokio")]
+pub struct Windowed<S, Ks, K> {
+    stream: Pin<Box<S>>,
+    key_selector: Ks,
+    interval: Interval,
+    counts: HashMap<K, usize>,
+    cumulative: bool,
+    received: bool,
+    done: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl<S, Ks, K> Windowed<S, Ks, K> {
+    /// Keeps counting across windows, so every map holds the totals since
+    /// the start instead of the last window alone.
+    pub fn cumulative(mut self) -> Self {
+        self.cumulative = true;
+        self
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, Ks, K> Windowed<S, Ks, K>
+where
+    K: Clone,
+{
+    fn snapshot(&mut self) -> HashMap<K, usize> {
+        self.received = false;
+
+        if self.cumulative {
+            self.counts.clone()
+        } else {
+            mem::take(&mut self.counts)
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, Ks, K> Stream for Windowed<S, Ks, K>
+where
+    S: Stream,
+    Ks: FnMut(&S::Item) -> K + Unpin,
+    K: Eq + Hash + Clone + Unpin,
+{
+    type Item = HashMap<K, usize>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            // The boundary is checked before every item, so an item that is
+            // only picked up after its window closed goes to the next one.
+            if this.interval.poll_tick(cx).is_ready() {
+                return Poll::Ready(Some(this.snapshot()));
+            }
+
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    *this.counts.entry((this.key_selector)(&item)).or_insert(0) += 1;
+                    this.received = true;
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+
+                    return Poll::Ready(this.received.then(|| this.snapshot()));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+    use futures::{executor::block_on, stream, StreamExt};
+    use tokio::{task, time};
+
+    use super::IntoGroupingStream;
+    use crate::lazy::IntoGrouping;
+
+    #[test]
+    fn test_grouping_aggregate() {
+        let values = stream::iter(3..=9).grouping_by(|i| *i % 3);
+        let aggregated = block_on(values.aggregate(|key, accumulator: Option<String>, item| {
+            if let Some(mut accumulator) = accumulator {
+                accumulator.push_str(&format!("-{}", item));
+                accumulator
+            } else {
+                format!("{}:{}", key, item)
+            }
+        }));
+
+        assert_eq!(
+            aggregated,
+            HashMap::from([
+                (0, "0:3-6-9".to_string()),
+                (1, "1:4-7".to_string()),
+                (2, "2:5-8".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fold_with_key() {
+        let fruits = stream::iter(vec![
+            "cherry",
+            "blueberry",
+            "citrus",
+            "apple",
+            "apricot",
+            "banana",
+            "coconut",
+        ])
+        .grouping_by(|fruit_name| fruit_name.chars().next().unwrap());
+        let even_fruits = block_on(fruits.fold_with_key(
+            |_, _| vec![],
+            |_, mut accumulator, item| {
+                if item.len() % 2 == 0 {
+                    accumulator.push(item.to_string());
+                }
+
+                accumulator
+            },
+        ));
+
+        assert_eq!(
+            even_fruits,
+            HashMap::from([
+                ('a', vec![]),
+                ('b', vec!["banana".to_string()]),
+                ('c', vec!["cherry".to_string(), "citrus".to_string()])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fold_matches_sync() {
+        let words = ["kafka", "topic", "offset", "key", "partition", "ack"];
+        let sum_len = |total: usize, word: &str| total + word.len();
+
+        assert_eq!(
+            block_on(
+                stream::iter(words)
+                    .grouping_by(|word| word.len() % 2)
+                    .fold(0, sum_len)
+            ),
+            words
+                .into_iter()
+                .grouping_by(|word| word.len() % 2)
+                .fold(0, sum_len)
+        );
+    }
+
+    #[test]
+    fn test_aggregate_updates() {
+        let sum =
+            |_: &char, total: Option<u32>, (_, amount): (char, u32)| total.unwrap_or(0) + amount;
+        let payments = [('a', 5), ('b', 1), ('a', 2), ('b', 4), ('a', 1)];
+        let updates = block_on(
+            stream::iter(payments)
+                .grouping_by(|(account, _)| *account)
+                .aggregate_updates(sum)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            updates,
+            payments
+                .into_iter()
+                .grouping_by(|(account, _)| *account)
+                .scan_aggregate(sum)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            updates.into_iter().collect::<HashMap<_, _>>(),
+            block_on(
+                stream::iter(payments)
+                    .grouping_by(|(account, _)| *account)
+                    .aggregate(sum)
+            )
+        );
+    }
+
+    #[test]
+    fn test_consecutive_groups() {
+        let rows = [("a", 1), ("a", 2), ("b", 3), ("c", 4), ("c", 5)];
+        let runs = block_on(
+            stream::iter(rows)
+                .grouping_by(|(key, _)| *key)
+                .consecutive_groups()
+                .map(|(key, items)| (key, items.len()))
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(runs, [("a", 2), ("b", 1), ("c", 2)]);
+    }
+
+    #[test]
+    fn test_consecutive_fold() {
+        let rows = [("a", 1), ("b", 2), ("b", 3), ("a", 4), ("a", 5)];
+        let sums = block_on(
+            stream::iter(rows)
+                .grouping_by(|(key, _)| *key)
+                .consecutive_fold(0, |sum, (_, n)| sum + n)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(sums, [("a", 1), ("b", 5), ("a", 9)]);
+        assert!(block_on(
+            stream::iter(Vec::<u8>::new())
+                .grouping_by(|n| *n)
+                .consecutive_groups()
+                .collect::<Vec<_>>()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_each_count_matches_sync() {
+        assert_eq!(
+            block_on(stream::iter(0..100).grouping_by(|i| i % 7).each_count()),
+            (0..100).grouping_by(|i| i % 7).each_count()
+        );
+        assert!(block_on(
+            stream::iter(Vec::<u8>::new())
+                .grouping_by(|i| *i)
+                .each_count()
+        )
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_grouping_by_async() {
+        let calls = RefCell::new(Vec::new());
+        let calls = &calls;
+        let groups = stream::iter(0..6u32)
+            .grouping_by_async(|user: &u32| {
+                let user = *user;
+
+                async move {
+                    task::yield_now().await;
+                    calls.borrow_mut().push(("key", user));
+                    user % 2
+                }
+            })
+            .aggregate_async(|_, users: Option<Vec<u32>>, user| async move {
+                task::yield_now().await;
+                calls.borrow_mut().push(("aggregate", user));
+
+                let mut users = users.unwrap_or_default();
+
+                users.push(user);
+                users
+            })
+            .await;
+
+        assert_eq!(
+            groups,
+            HashMap::from([(0, vec![0, 2, 4]), (1, vec![1, 3, 5])])
+        );
+        assert_eq!(
+            *calls.borrow(),
+            (0..6)
+                .flat_map(|user| [("key", user), ("aggregate", user)])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_grouping_by_async_buffered() {
+        let groups = stream::iter([30u64, 10, 20, 40])
+            .grouping_by_async(|delay: &u64| {
+                let delay = *delay;
+
+                async move {
+                    time::sleep(Duration::from_millis(delay)).await;
+                    delay >= 25
+                }
+            })
+            .buffered(4)
+            .aggregate(|_, delays: Option<Vec<u64>>, delay| {
+                let mut delays = delays.unwrap_or_default();
+
+                delays.push(delay);
+                delays
+            })
+            .await;
+
+        assert_eq!(
+            groups,
+            HashMap::from([(false, vec![10, 20]), (true, vec![30, 40])])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_try_aggregate_async() {
+        let parse = |_: &&str, total: Option<u32>, reading: &str| {
+            let parsed = reading.parse::<u32>();
+
+            async move { parsed.map(|reading| total.unwrap_or(0) + reading) }
+        };
+
+        assert_eq!(
+            stream::iter(["1", "2", "3"])
+                .grouping_by(|_| "sensor")
+                .try_aggregate_async(parse)
+                .await,
+            Ok(HashMap::from([("sensor", 6)]))
+        );
+        assert!(stream::iter(["1", "x", "3"])
+            .grouping_by_async(|_| async { "sensor" })
+            .try_aggregate_async(parse)
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn test_windowed() {
+        use futures::channel::mpsc;
+
+        let (tx, rx) = mpsc::unbounded();
+        let mut windows = rx
+            .grouping_by(|event: &&str| *event)
+            .windowed(Duration::from_secs(1));
+
+        tx.unbounded_send("click").unwrap();
+        tx.unbounded_send("view").unwrap();
+        tx.unbounded_send("click").unwrap();
+        assert_eq!(
+            windows.next().await,
+            Some(HashMap::from([("click", 2), ("view", 1)]))
+        );
+
+        tx.unbounded_send("view").unwrap();
+        assert_eq!(windows.next().await, Some(HashMap::from([("view", 1)])));
+        assert_eq!(windows.next().await, Some(HashMap::new()));
+
+        tx.unbounded_send("click").unwrap();
+        drop(tx);
+        assert_eq!(windows.next().await, Some(HashMap::from([("click", 1)])));
+        assert_eq!(windows.next().await, None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn test_windowed_cumulative() {
+        use futures::channel::mpsc;
+
+        let (tx, rx) = mpsc::unbounded();
+        let mut windows = rx
+            .grouping_by(|n: &u32| n % 2)
+            .windowed(Duration::from_millis(100))
+            .cumulative();
+
+        tx.unbounded_send(1).unwrap();
+        assert_eq!(windows.next().await, Some(HashMap::from([(1, 1)])));
+
+        tx.unbounded_send(2).unwrap();
+        tx.unbounded_send(3).unwrap();
+        assert_eq!(windows.next().await, Some(HashMap::from([(0, 1), (1, 2)])));
+        assert_eq!(windows.next().await, Some(HashMap::from([(0, 1), (1, 2)])));
+    }
+}
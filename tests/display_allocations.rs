@@ -0,0 +1,69 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use metamorphosis::eager::IntoGrouping;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+#[test]
+fn display_allocations_do_not_scale_with_groups() {
+    let few = (0..1_000).collect::<Vec<u32>>().grouping_by(|i| i % 2);
+    let many = (0..1_000).collect::<Vec<u32>>().grouping_by(|i| i % 500);
+    let mut out = String::with_capacity(1 << 16);
+
+    let few_allocations = allocations_during(|| write!(out, "{}", few).unwrap());
+    out.clear();
+    let many_allocations = allocations_during(|| write!(out, "{}", many).unwrap());
+
+    assert!(out.starts_with("{0=[0, 500], 1=[1, 501], "));
+    // Only the key table grows with the number of groups (logarithmically);
+    // one allocation per group would put this in the hundreds.
+    assert!(few_allocations <= 8);
+    assert!(many_allocations <= few_allocations + 16);
+}
+
+#[test]
+fn display_keeps_first_seen_group_order() {
+    let words = vec!["banana", "apple", "blueberry", "cherry", "avocado"];
+
+    assert_eq!(
+        words.grouping_by(|w| w.chars().next().unwrap()).to_string(),
+        "{b=[banana, blueberry], a=[apple, avocado], c=[cherry]}"
+    );
+    assert_eq!(Vec::<u8>::new().grouping_by(|i| *i).to_string(), "{}");
+}
@@ -0,0 +1,41 @@
+#![allow(deprecated)]
+
+use std::marker::PhantomData;
+
+use metamorphosis::{akt1, akt2, eager, lazy};
+
+fn assert_same_type<T>(_: PhantomData<T>, _: PhantomData<T>) {}
+
+type Selector = fn(&u8) -> u8;
+
+#[test]
+fn test_deprecated_paths_resolve_to_renamed_modules() {
+    assert_same_type(
+        PhantomData::<akt1::GroupingImpl<u8, u8, Selector>>,
+        PhantomData::<eager::GroupingImpl<u8, u8, Selector>>,
+    );
+    assert_same_type(
+        PhantomData::<akt2::Grouping<std::vec::IntoIter<u8>, Selector, u8>>,
+        PhantomData::<lazy::Grouping<std::vec::IntoIter<u8>, Selector, u8>>,
+    );
+    assert_same_type(
+        PhantomData::<metamorphosis::LazyGrouping<std::vec::IntoIter<u8>, Selector, u8>>,
+        PhantomData::<lazy::Grouping<std::vec::IntoIter<u8>, Selector, u8>>,
+    );
+
+    let eager = {
+        use akt1::{Grouping, IntoGrouping};
+
+        vec![1u8, 2, 3, 4].grouping_by(|n| n % 2).each_count()
+    };
+    let lazy = {
+        use akt2::IntoGrouping;
+
+        vec![1u8, 2, 3, 4]
+            .into_iter()
+            .grouping_by(|n| n % 2)
+            .each_count()
+    };
+
+    assert_eq!(eager, lazy);
+}